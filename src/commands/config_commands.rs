@@ -0,0 +1,178 @@
+use serde_json::{Map, Value};
+use url::Url;
+
+use crate::{AppConfig, utils::{config::store_config, secret_store::obtain_passphrase}};
+
+// Keys that "config set" may change.  The rest are maintained by JellyRoller itself.
+const EDITABLE_KEYS: [&str; 5] = ["api_key", "comfy", "device_name", "passphrase_file", "server_url"];
+
+pub fn command_config_path(cfg: &AppConfig) {
+    println!("{}", cfg.config_path.display());
+}
+
+pub fn command_config_show(cfg: &AppConfig) {
+    let mut values = config_to_map(cfg);
+    redact_secrets(&mut values);
+    println!("{}", serde_json::to_string_pretty(&values).unwrap());
+}
+
+pub fn command_config_get(cfg: &AppConfig, key: &str, reveal: bool) {
+    let mut values = config_to_map(cfg);
+    if !reveal {
+        redact_secrets(&mut values);
+    }
+    match values.get(key) {
+        Some(Value::String(s)) => println!("{s}"),
+        Some(v) => println!("{v}"),
+        None => {
+            eprintln!("Unknown configuration key \"{key}\".  Valid keys are: {}", valid_keys(&values));
+            std::process::exit(1);
+        }
+    }
+}
+
+pub fn command_config_set(cfg: &AppConfig, key: &str, value: &str) {
+    let mut values = config_to_map(cfg);
+    if values.contains_key(key) && !EDITABLE_KEYS.contains(&key) {
+        eprintln!("Configuration key \"{key}\" is managed by JellyRoller and cannot be set.  Editable keys are: {}", EDITABLE_KEYS.join(", "));
+        std::process::exit(1);
+    }
+    let new_value = match values.get(key) {
+        Some(Value::Bool(_)) => match value.to_lowercase().parse::<bool>() {
+            Ok(b) => Value::Bool(b),
+            Err(_) => {
+                eprintln!("Configuration key \"{key}\" expects true or false.");
+                std::process::exit(1);
+            }
+        },
+        Some(Value::Number(_)) => match value.parse::<i64>() {
+            Ok(n) => Value::from(n),
+            Err(_) => {
+                eprintln!("Configuration key \"{key}\" expects a number.");
+                std::process::exit(1);
+            }
+        },
+        Some(_) => Value::String(value.to_owned()),
+        None => {
+            eprintln!("Unknown configuration key \"{key}\".  Editable keys are: {}", EDITABLE_KEYS.join(", "));
+            std::process::exit(1);
+        }
+    };
+    values.insert(key.to_owned(), new_value);
+
+    let mut updated: AppConfig = match serde_json::from_value(Value::Object(values)) {
+        Err(e) => {
+            eprintln!("Unable to apply configuration change, {e}");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    };
+    updated.config_path.clone_from(&cfg.config_path);
+    updated.passphrase.clone_from(&cfg.passphrase);
+    store_config(&updated);
+    println!("Configuration key \"{key}\" updated.");
+}
+
+pub fn command_config_validate(cfg: &AppConfig) {
+    let problems = validate_config(cfg);
+    if problems.is_empty() {
+        println!("Configuration at {} is valid.", cfg.config_path.display());
+    } else {
+        for problem in &problems {
+            eprintln!("[ERROR] {problem}");
+        }
+        std::process::exit(1);
+    }
+}
+
+pub fn command_config_encrypt(mut cfg: AppConfig) {
+    if !cfg.encrypted_api_key.is_empty() {
+        eprintln!("The API key is already encrypted.");
+        std::process::exit(1);
+    }
+    if cfg.api_key.is_empty() || cfg.api_key == "Unknown" {
+        eprintln!("There is no API key to encrypt.  Please configure JellyRoller first.");
+        std::process::exit(1);
+    }
+    cfg.passphrase = Some(obtain_passphrase(&cfg, true));
+    store_config(&cfg);
+    println!("API key encrypted.");
+}
+
+pub fn command_config_decrypt(mut cfg: AppConfig) {
+    if cfg.encrypted_api_key.is_empty() {
+        eprintln!("The API key is not encrypted.");
+        std::process::exit(1);
+    }
+    cfg.passphrase = None;
+    cfg.encrypted_api_key = String::new();
+    store_config(&cfg);
+    println!("API key decrypted.");
+}
+
+/*
+    The following section contains additional
+    functions that are used to support the config_commands
+    base functions.
+*/
+
+///
+/// Converts the configuration into a key/value map so that individual settings can be addressed by name.
+///
+fn config_to_map(cfg: &AppConfig) -> Map<String, Value> {
+    match serde_json::to_value(cfg) {
+        Ok(Value::Object(map)) => map,
+        _ => {
+            eprintln!("Unable to read configuration values.");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn valid_keys(values: &Map<String, Value>) -> String {
+    values.keys().map(String::as_str).collect::<Vec<&str>>().join(", ")
+}
+
+fn redact_secrets(values: &mut Map<String, Value>) {
+    for key in ["api_key", "encrypted_api_key"] {
+        if let Some(secret) = values.get_mut(key) {
+            *secret = Value::String(redact(secret.as_str().unwrap_or_default()));
+        }
+    }
+}
+
+fn redact(secret: &str) -> String {
+    if secret.is_empty() || secret == "Unknown" {
+        secret.to_owned()
+    } else {
+        "********".to_owned()
+    }
+}
+
+///
+/// Checks the configuration for values that would prevent JellyRoller from talking to the server.
+///
+fn validate_config(cfg: &AppConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+    if cfg.status != "configured" {
+        problems.push("JellyRoller has not been configured.  Run 'jellyroller reconfigure'.".to_owned());
+    }
+    match Url::parse(&cfg.server_url) {
+        Ok(u) if u.scheme() == "http" || u.scheme() == "https" => {}
+        Ok(_) => problems.push(format!("server_url \"{}\" must use http or https.", cfg.server_url)),
+        Err(e) => problems.push(format!("server_url \"{}\" is not a valid URL, {e}.", cfg.server_url)),
+    }
+    if cfg.server_url.ends_with('/') {
+        problems.push("server_url should not end with a trailing '/'.".to_owned());
+    }
+    if cfg.api_key.is_empty() || cfg.api_key == "Unknown" {
+        problems.push("api_key is not set.".to_owned());
+    }
+    if !cfg.passphrase_file.is_empty() && !std::path::Path::new(&cfg.passphrase_file).exists() {
+        problems.push(format!("passphrase_file \"{}\" does not exist.", cfg.passphrase_file));
+    }
+    if cfg.token != "apiKey" {
+        problems.push("token does not indicate an API key is in use.  Run 'jellyroller reconfigure'.".to_owned());
+    }
+    problems
+}
@@ -1,6 +1,9 @@
+use std::collections::HashMap;
 use std::env;
 
-use crate::{ AppConfig, ReportType, ActivityDetails, MovieDetails, OutputFormat, utils::output_writer::export_data, system_actions::{get_activity, export_library, return_server_info, get_log_filenames}, entities::server_info::ServerInfo, entities::log_details::LogDetails};
+use chrono::{DateTime, Utc};
+
+use crate::{ AppConfig, ReportType, ActivityDetails, MovieDetails, OutputFormat, utils::{common::parse_jellyfin_date, output_writer::export_data}, system_actions::{get_activity, export_library, return_server_info, get_log_filenames}, entities::server_info::ServerInfo, entities::activity_details::Item as ActivityItem, entities::log_details::LogDetails, entities::audit_details::UserAudit, entities::user_details::UserDetails, user_actions::UserList};
 
 pub fn command_generate_report(cfg: &AppConfig) {
     let info = return_server_info(ServerInfo::new(
@@ -99,4 +102,128 @@ pub fn command_create_report(cfg: &AppConfig, report_type: &ReportType, limit: &
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Thresholds used by `report users-audit` to decide which accounts are flagged.
+pub struct AuditThresholds {
+    pub inactive_days: i64,
+    pub failed_logins: i64,
+}
+
+pub fn command_report_users_audit(cfg: &AppConfig, thresholds: &AuditThresholds, activity_limit: &str, flagged_only: bool, output_format: &OutputFormat, users_endpoint: &str) {
+    let users = match UserList::list_users(UserList::new(users_endpoint, &cfg.server_url, &cfg.api_key)) {
+        Err(e) => {
+            eprintln!("Unable to gather users, {e}");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    };
+    let activities = match get_activity(
+        ServerInfo::new("/System/ActivityLog/Entries", &cfg.server_url, &cfg.api_key),
+        activity_limit,
+    ) {
+        Err(e) => {
+            eprintln!("Unable to gather activity log entries, {e}");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    };
+
+    // Latest log entry per user.  Failed sign ins are not tied to a user id, only to the name that was tried.
+    let mut last_logged: HashMap<&str, DateTime<Utc>> = HashMap::new();
+    let mut failed_attempts: Vec<&ActivityItem> = Vec::new();
+    for item in &activities.items {
+        if item.type_field == "AuthenticationFailed" {
+            failed_attempts.push(item);
+            continue;
+        }
+        if let Some(date) = parse_jellyfin_date(Some(&item.date)) && !item.user_id.is_empty() {
+            let latest = last_logged.entry(&item.user_id).or_insert(date);
+            if date > *latest {
+                *latest = date;
+            }
+        }
+    }
+
+    let now = Utc::now();
+    let mut rows: Vec<UserAudit> = users
+        .iter()
+        .map(|user| {
+            let failed = failed_attempts.iter().filter(|item| is_failed_login_of(item, user)).count();
+            audit_user(user, last_logged.get(user.id.as_str()).copied(), i64::try_from(failed).unwrap_or(i64::MAX), thresholds, now)
+        })
+        .collect();
+    if flagged_only {
+        rows.retain(|row| !row.flags.is_empty());
+    }
+
+    match output_format {
+        OutputFormat::Json => UserAudit::json_print(&rows),
+        OutputFormat::Csv => UserAudit::csv_print(&rows),
+        OutputFormat::Table => UserAudit::table_print(rows),
+    }
+}
+
+/*
+    The following section contains additional
+    functions that are used to support the log_commands
+    base functions.
+*/
+
+fn audit_user(user: &UserDetails, logged: Option<DateTime<Utc>>, failed_in_log: i64, thresholds: &AuditThresholds, now: DateTime<Utc>) -> UserAudit {
+    let last_login = parse_jellyfin_date(user.last_login_date.as_deref());
+    let last_activity = [parse_jellyfin_date(user.last_activity_date.as_deref()), last_login, logged]
+        .into_iter()
+        .flatten()
+        .max();
+    let days_inactive = last_activity.map(|date| (now - date).num_days());
+
+    let mut flags = Vec::new();
+    if user.policy.is_administrator {
+        flags.push("admin");
+    }
+    if last_login.is_none() {
+        flags.push("never-logged-in");
+    }
+    if days_inactive.is_some_and(|days| days >= thresholds.inactive_days) {
+        flags.push("inactive");
+    }
+    if !user.has_password {
+        flags.push("no-password");
+    }
+    if user.policy.enable_remote_access {
+        flags.push("remote-access");
+    }
+    if user.policy.invalid_login_attempt_count.max(failed_in_log) >= thresholds.failed_logins {
+        flags.push("failed-logins");
+    }
+
+    UserAudit {
+        username: user.name.clone(),
+        admin: user.policy.is_administrator,
+        disabled: user.policy.is_disabled,
+        has_password: user.has_password,
+        remote_access: user.policy.enable_remote_access,
+        last_login: format_date(last_login),
+        last_activity: format_date(last_activity),
+        days_inactive,
+        invalid_login_attempts: user.policy.invalid_login_attempt_count,
+        failed_logins_in_log: failed_in_log,
+        flags: flags.into_iter().map(str::to_owned).collect(),
+    }
+}
+
+fn format_date(date: Option<DateTime<Utc>>) -> String {
+    date.map_or_else(|| "never".to_owned(), |d| d.format("%Y-%m-%d %H:%M").to_string())
+}
+
+///
+/// Failed sign in entries read like "Failed login try from bob" and usually carry no user id, so unless they do
+/// the whole name after "from" has to match the username.
+///
+fn is_failed_login_of(item: &ActivityItem, user: &UserDetails) -> bool {
+    if !item.user_id.is_empty() {
+        return item.user_id == user.id;
+    }
+    item.name.split_once(" from ").is_some_and(|(_, name)| name.trim().eq_ignore_ascii_case(&user.name))
+}
@@ -193,7 +193,7 @@ pub fn command_library_enable_disable(cfg: &AppConfig, library: String, status:
 ///
 /// Executes a search with the passed parameters.
 ///
-fn execute_search(
+pub fn execute_search(
     term: &str,
     mediatype: &str,
     parentid: &str,
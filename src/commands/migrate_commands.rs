@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+
+use crate::{AppConfig, PasswordGenerationArgs,
+    commands::user_commands::find_user,
+    entities::{server_info::ServerInfo, user_details::UserDetails},
+    system_actions::{get_channels, get_libraries, get_user_image, set_user_image},
+    user_actions::{UserList, UserWithPass},
+    utils::{common::{exit_on_error, image_bytes_to_base64}, config::load_profile, credentials::{Credential, export_credentials, generate_password}}};
+
+const USER_IMAGE: &str = "/Users/{userId}/Images/Primary";
+
+/// Options for `users migrate`.
+pub struct MigrationOptions<'a> {
+    /// Profile to copy the users from, or the active configuration when not given.
+    pub from: Option<&'a str>,
+    pub to: &'a str,
+    /// Users to copy, or every user when empty.
+    pub usernames: &'a [String],
+    pub generate_passwords: bool,
+    pub generation: &'a PasswordGenerationArgs,
+    pub dry_run: bool,
+}
+
+// Servers and endpoints used while migrating users.
+struct MigrationContext<'a> {
+    source: AppConfig,
+    target: AppConfig,
+    users_endpoint: &'a str,
+    user_policy_endpoint: &'a str,
+    user_configuration_endpoint: &'a str,
+}
+
+// Ids of libraries or channels on the source server translated to the target server by name.
+struct IdMapping {
+    kind: &'static str,
+    names: HashMap<String, String>,
+    targets: HashMap<String, String>,
+}
+
+impl IdMapping {
+    fn new(kind: &'static str, source: Vec<(String, String)>, target: Vec<(String, String)>) -> IdMapping {
+        IdMapping {
+            kind,
+            names: source.into_iter().collect(),
+            targets: target.into_iter().map(|(id, name)| (name.to_lowercase(), id)).collect(),
+        }
+    }
+
+    ///
+    /// Translates a list of ids, leaving out and reporting any that do not exist on the target.
+    ///
+    fn translate(&self, ids: &[String], field: &str, unmapped: &mut Vec<String>) -> Vec<String> {
+        let mut translated = Vec::new();
+        for id in ids {
+            match self.names.get(id) {
+                None => unmapped.push(format!("{field}: unknown {} id {id} on the source server", self.kind)),
+                Some(name) => match self.targets.get(&name.to_lowercase()) {
+                    None => unmapped.push(format!("{field}: {} \"{name}\" does not exist on the target server", self.kind)),
+                    Some(target) => translated.push(target.clone()),
+                },
+            }
+        }
+        translated
+    }
+}
+
+pub fn command_users_migrate(cfg: &AppConfig, options: &MigrationOptions, users_endpoint: &str, user_policy_endpoint: &str, user_configuration_endpoint: &str) {
+    let source = match options.from {
+        Some(profile) => exit_on_error(load_profile(profile)),
+        None => cfg.clone(),
+    };
+    let target = exit_on_error(load_profile(options.to));
+    if source.server_url.trim_end_matches('/') == target.server_url.trim_end_matches('/') {
+        eprintln!("The source and target profiles point at the same server ({}).", source.server_url);
+        std::process::exit(1);
+    }
+    let context = MigrationContext { source, target, users_endpoint, user_policy_endpoint, user_configuration_endpoint };
+    let (source, target) = (&context.source, &context.target);
+
+    let source_users = exit_on_error(
+        UserList::list_users(UserList::new(users_endpoint, &source.server_url, &source.api_key))
+            .map_err(|e| format!("Unable to gather users from {}.  {e}", source.server_url)),
+    );
+    let target_users = exit_on_error(
+        UserList::list_users(UserList::new(users_endpoint, &target.server_url, &target.api_key))
+            .map_err(|e| format!("Unable to gather users from {}.  {e}", target.server_url)),
+    );
+    let missing: Vec<&str> = options
+        .usernames
+        .iter()
+        .filter(|n| !source_users.iter().any(|u| u.name.eq_ignore_ascii_case(n)))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        eprintln!("Unknown user(s) on {}: {}", source.server_url, missing.join(", "));
+        std::process::exit(1);
+    }
+    let users: Vec<&UserDetails> = source_users
+        .iter()
+        .filter(|u| options.usernames.is_empty() || options.usernames.iter().any(|n| u.name.eq_ignore_ascii_case(n)))
+        .collect();
+
+    let libraries = IdMapping::new("library", exit_on_error(library_ids(source)), exit_on_error(library_ids(target)));
+    let channels = IdMapping::new("channel", exit_on_error(channel_ids(source)), exit_on_error(channel_ids(target)));
+
+    println!("Migrating {} user(s) from {} to {}.", users.len(), source.server_url, target.server_url);
+    let mut credentials: Vec<Credential> = Vec::new();
+    let mut report: Vec<(String, Vec<String>)> = Vec::new();
+    let (mut migrated, mut incomplete, mut skipped, mut failed) = (0, 0, 0, 0);
+    for user in users {
+        if target_users.iter().any(|u| u.name.eq_ignore_ascii_case(&user.name)) {
+            println!("  {} already exists on the target, skipped.", user.name);
+            skipped += 1;
+            continue;
+        }
+        let mut unmapped = Vec::new();
+        let mut translated = translate_user(user, &libraries, &channels, &mut unmapped);
+        // Passwords cannot be copied, so a protected account would otherwise arrive without one.
+        let disable = user.has_password && !options.generate_passwords && !user.policy.is_disabled;
+        if disable {
+            translated.policy.is_disabled = true;
+            unmapped.push("Password: passwords cannot be copied, the user is created disabled.  Use reset-password and enable-user, or --generate-passwords".to_owned());
+        }
+        if options.dry_run {
+            println!("  {} would be created{}.", user.name, if disable { " disabled" } else { "" });
+        } else {
+            let password = if options.generate_passwords { generate_password(options.generation) } else { String::new() };
+            match migrate_user(&context, &translated, &password, &mut unmapped) {
+                Err(e) => {
+                    eprintln!("[ERROR] Unable to migrate {}.  {e}", user.name);
+                    failed += 1;
+                    continue;
+                }
+                Ok(true) => {
+                    println!("  {} migrated{}.", user.name, if disable { ", disabled until it has a password" } else { "" });
+                    migrated += 1;
+                }
+                Ok(false) => {
+                    println!("  {} created, but not every setting could be applied.", user.name);
+                    incomplete += 1;
+                }
+            }
+            if options.generate_passwords {
+                credentials.push(Credential { username: user.name.clone(), password });
+            }
+        }
+        if !unmapped.is_empty() {
+            report.push((user.name.clone(), unmapped));
+        }
+    }
+
+    if !options.dry_run {
+        export_credentials(&credentials, options.generation);
+        println!("Migration complete: {migrated} migrated, {incomplete} incomplete, {skipped} skipped, {failed} failed.");
+    }
+    if report.is_empty() {
+        println!("Every library, channel and setting was translated.");
+    } else {
+        println!("\nThe following could not be translated:");
+        for (username, items) in &report {
+            println!("  {username}:");
+            for item in items {
+                println!("    - {item}");
+            }
+        }
+    }
+    if failed + incomplete > 0 {
+        std::process::exit(1);
+    }
+}
+
+/*
+    The following section contains additional
+    functions that are used to support the migrate_commands
+    base functions.
+*/
+
+fn library_ids(cfg: &AppConfig) -> Result<Vec<(String, String)>, String> {
+    let libraries = get_libraries(ServerInfo::new("/Library/VirtualFolders", &cfg.server_url, &cfg.api_key))
+        .map_err(|e| format!("Unable to get libraries from {}, {e}", cfg.server_url))?;
+    Ok(libraries.into_iter().map(|l| (l.item_id, l.name)).collect())
+}
+
+fn channel_ids(cfg: &AppConfig) -> Result<Vec<(String, String)>, String> {
+    let channels = get_channels(ServerInfo::new("/Channels", &cfg.server_url, &cfg.api_key))
+        .map_err(|e| format!("Unable to get channels from {}, {e}", cfg.server_url))?;
+    Ok(channels.items.into_iter().map(|c| (c.id, c.name)).collect())
+}
+
+///
+/// Copies the policy and preferences of a user with every library and channel id translated for the target.
+///
+fn translate_user(user: &UserDetails, libraries: &IdMapping, channels: &IdMapping, unmapped: &mut Vec<String>) -> UserDetails {
+    let mut user = user.clone();
+    let policy = &mut user.policy;
+    if !policy.enable_all_folders {
+        policy.enabled_folders = libraries.translate(&policy.enabled_folders, "EnabledFolders", unmapped);
+    }
+    policy.enable_content_deletion_from_folders =
+        libraries.translate(&policy.enable_content_deletion_from_folders, "EnableContentDeletionFromFolders", unmapped);
+    policy.blocked_media_folders = libraries.translate(&policy.blocked_media_folders, "BlockedMediaFolders", unmapped);
+    if !policy.enable_all_channels {
+        policy.enabled_channels = channels.translate(&policy.enabled_channels, "EnabledChannels", unmapped);
+    }
+    policy.blocked_channels = channels.translate(&policy.blocked_channels, "BlockedChannels", unmapped);
+    // Devices are tied to the server they signed in to.
+    if !policy.enable_all_devices {
+        unmapped.push("EnabledDevices: device restrictions cannot be copied, the user may use any device".to_owned());
+        policy.enable_all_devices = true;
+        policy.enabled_devices.clear();
+    }
+
+    let configuration = &mut user.configuration;
+    configuration.ordered_views = libraries.translate(&configuration.ordered_views, "OrderedViews", unmapped);
+    configuration.latest_items_excludes = libraries.translate(&configuration.latest_items_excludes, "LatestItemsExcludes", unmapped);
+    configuration.my_media_excludes = libraries.translate(&configuration.my_media_excludes, "MyMediaExcludes", unmapped);
+    configuration.grouped_folders = libraries.translate(&configuration.grouped_folders, "GroupedFolders", unmapped);
+    user
+}
+
+///
+/// Creates the user on the target and applies the translated policy, preferences and profile picture.  Returns
+/// whether everything was applied; failures after the user exists are added to the report instead.
+///
+fn migrate_user(context: &MigrationContext, user: &UserDetails, password: &str, unmapped: &mut Vec<String>) -> Result<bool, String> {
+    let (source, target) = (&context.source, &context.target);
+    UserWithPass::create_user(UserWithPass::new(
+        Some(user.name.clone()),
+        Some(password.to_owned()),
+        None,
+        format!("{}/Users/New", target.server_url),
+        target.api_key.clone(),
+    ))
+    .map_err(|e| e.to_string())?;
+    let created = find_user(target, context.users_endpoint, &user.name)?;
+
+    let mut complete = true;
+    let mut policy = user.policy.clone();
+    for schedule in &mut policy.access_schedules {
+        schedule.user_id.clone_from(&created.id);
+    }
+    if let Err(e) = UserList::update_user_policy(UserList::new(context.user_policy_endpoint, &target.server_url, &target.api_key), &policy, &created.id) {
+        // An account that should be disabled must not be left usable, possibly without a password.
+        if policy.is_disabled {
+            UserWithPass::delete_user(UserWithPass::new(
+                Some(created.name.clone()),
+                None,
+                None,
+                format!("{}/Users/{}", target.server_url, created.id),
+                target.api_key.clone(),
+            ))
+            .map_err(|d| format!("Unable to disable the new account ({e}) or remove it again ({d}), remove it by hand"))?;
+            return Err(format!("Unable to disable the new account, so it was removed again.  {e}"));
+        }
+        unmapped.push(format!("Policy: {e}"));
+        complete = false;
+    }
+    if let Err(e) = UserList::update_user_configuration(UserList::new(context.user_configuration_endpoint, &target.server_url, &target.api_key), &user.configuration, &created.id) {
+        unmapped.push(format!("Configuration: {e}"));
+        complete = false;
+    }
+    if !user.primary_image_tag.is_empty() {
+        let result = get_user_image(ServerInfo::new(USER_IMAGE, &source.server_url, &source.api_key), &user.id)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| image_bytes_to_base64(&bytes))
+            .and_then(|image| {
+                set_user_image(ServerInfo::new(USER_IMAGE, &target.server_url, &target.api_key), &created.id, image)
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            unmapped.push(format!("Profile picture: {e}"));
+            complete = false;
+        }
+    }
+    Ok(complete)
+}
@@ -1,4 +1,9 @@
+pub mod config_commands;
 pub mod log_commands;
 pub mod media_commands;
+pub mod migrate_commands;
+pub mod parental_commands;
+pub mod role_commands;
 pub mod server_commands;
-pub mod user_commands;
\ No newline at end of file
+pub mod user_commands;
+pub mod watch_commands;
\ No newline at end of file
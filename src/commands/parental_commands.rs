@@ -0,0 +1,154 @@
+use crate::{AppConfig, OutputFormat, UnratedItem,
+    commands::user_commands::find_user,
+    entities::{parental_details::{ParentalRating, ParentalRestrictions}, server_info::ServerInfo, user_details::Policy},
+    system_actions::get_parental_ratings,
+    user_actions::UserList,
+    utils::common::exit_on_error};
+
+/// Requested changes to the parental controls of users.
+pub struct ParentalChanges<'a> {
+    pub max_rating: Option<&'a str>,
+    pub block_tags: &'a [String],
+    pub unblock_tags: &'a [String],
+    pub block_unrated: &'a [UnratedItem],
+    pub allow_unrated: &'a [UnratedItem],
+}
+
+pub fn command_parental_set(cfg: &AppConfig, usernames: &[String], changes: &ParentalChanges, users_endpoint: &str, user_policy_endpoint: &str, ratings_endpoint: &str) {
+    if changes.max_rating.is_none()
+        && changes.block_tags.is_empty()
+        && changes.unblock_tags.is_empty()
+        && changes.block_unrated.is_empty()
+        && changes.allow_unrated.is_empty()
+    {
+        eprintln!("Nothing to change.  Use --max-rating, --block-tag, --unblock-tag, --block-unrated or --allow-unrated.");
+        std::process::exit(1);
+    }
+    let ratings = exit_on_error(fetch_ratings(cfg, ratings_endpoint));
+    let max_rating = changes.max_rating.map(|name| exit_on_error(resolve_rating(&ratings, name)));
+
+    let mut failed = false;
+    for username in usernames {
+        let result = find_user(cfg, users_endpoint, username).and_then(|user| {
+            let mut policy = user.policy;
+            let before = restrictions(&user.name, &policy, &ratings);
+            if let Some(rating) = max_rating {
+                policy.max_parental_rating = rating;
+            }
+            for tag in changes.block_tags {
+                if !policy.blocked_tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                    policy.blocked_tags.push(tag.clone());
+                }
+            }
+            policy.blocked_tags.retain(|t| !changes.unblock_tags.iter().any(|u| u.eq_ignore_ascii_case(t)));
+            for item in changes.block_unrated {
+                let item = item.to_string();
+                if !policy.block_unrated_items.contains(&item) {
+                    policy.block_unrated_items.push(item);
+                }
+            }
+            policy.block_unrated_items.retain(|i| !changes.allow_unrated.iter().any(|a| a.to_string() == *i));
+
+            let after = restrictions(&user.name, &policy, &ratings);
+            if before == after {
+                println!("No changes for {}.", user.name);
+                return Ok(());
+            }
+            UserList::update_user_policy(UserList::new(user_policy_endpoint, &cfg.server_url, &cfg.api_key), &policy, &user.id)
+                .map_err(|e| e.to_string())?;
+            println!("Parental controls for {} updated.", user.name);
+            print_difference("max_rating", &before.max_rating, &after.max_rating);
+            print_difference("blocked_tags", &join_or_none(&before.blocked_tags), &join_or_none(&after.blocked_tags));
+            print_difference("blocked_unrated", &join_or_none(&before.blocked_unrated), &join_or_none(&after.blocked_unrated));
+            Ok(())
+        });
+        if let Err(e) = result {
+            eprintln!("[ERROR] {username}: {e}");
+            failed = true;
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+pub fn command_parental_report(cfg: &AppConfig, output_format: &OutputFormat, users_endpoint: &str, ratings_endpoint: &str) {
+    let ratings = exit_on_error(fetch_ratings(cfg, ratings_endpoint));
+    let users = match UserList::list_users(UserList::new(users_endpoint, &cfg.server_url, &cfg.api_key)) {
+        Err(_) => {
+            eprintln!("Unable to gather users.");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    };
+    let rows: Vec<ParentalRestrictions> = users.iter().map(|u| restrictions(&u.name, &u.policy, &ratings)).collect();
+
+    match output_format {
+        OutputFormat::Json => ParentalRestrictions::json_print(&rows),
+        OutputFormat::Csv => ParentalRestrictions::csv_print(&rows),
+        OutputFormat::Table => ParentalRestrictions::table_print(rows),
+    }
+}
+
+/*
+    The following section contains additional
+    functions that are used to support the parental_commands
+    base functions.
+*/
+
+fn fetch_ratings(cfg: &AppConfig, ratings_endpoint: &str) -> Result<Vec<ParentalRating>, String> {
+    get_parental_ratings(ServerInfo::new(ratings_endpoint, &cfg.server_url, &cfg.api_key))
+        .map_err(|e| format!("Unable to get parental ratings, {e}"))
+}
+
+///
+/// Converts a rating name such as "PG-13" into the value stored in the user policy.  "none" removes the limit.
+///
+fn resolve_rating(ratings: &[ParentalRating], name: &str) -> Result<Option<i64>, String> {
+    if name.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    if let Some(rating) = ratings.iter().find(|r| r.name.eq_ignore_ascii_case(name) && r.value.is_some()) {
+        return Ok(rating.value);
+    }
+    let mut known: Vec<&str> = ratings.iter().filter(|r| r.value.is_some()).map(|r| r.name.as_str()).collect();
+    known.dedup();
+    Err(format!("Unknown parental rating \"{name}\".  Known ratings are: none, {}", known.join(", ")))
+}
+
+///
+/// Shows a policy value as the rating names the server knows it by, e.g. "PG-13 (13)".
+///
+fn describe_rating(ratings: &[ParentalRating], value: Option<i64>) -> String {
+    match value {
+        None => "none".to_owned(),
+        Some(value) => match ratings.iter().find(|r| r.value == Some(value)) {
+            Some(rating) => format!("{} ({value})", rating.name),
+            None => value.to_string(),
+        },
+    }
+}
+
+fn restrictions(username: &str, policy: &Policy, ratings: &[ParentalRating]) -> ParentalRestrictions {
+    ParentalRestrictions {
+        username: username.to_owned(),
+        max_rating: describe_rating(ratings, policy.max_parental_rating),
+        blocked_tags: policy.blocked_tags.clone(),
+        blocked_unrated: policy.block_unrated_items.clone(),
+        schedules: policy.access_schedules.iter().map(ToString::to_string).collect(),
+    }
+}
+
+fn join_or_none(values: &[String]) -> String {
+    if values.is_empty() {
+        "none".to_owned()
+    } else {
+        values.join(", ")
+    }
+}
+
+fn print_difference(label: &str, before: &str, after: &str) {
+    if before != after {
+        println!("  {label}: {before} -> {after}");
+    }
+}
@@ -0,0 +1,172 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde_json::{Map, Value};
+
+use crate::{AppConfig,
+    UserSelector,
+    commands::user_commands::{policy_entry, policy_to_map, select_confirmed_users, select_users},
+    entities::user_details::Policy,
+    user_actions::UserList,
+    utils::{common::exit_on_error, config::store_config}};
+
+/// Exit code used by `role diff` when at least one user no longer matches the role.
+pub const ROLE_DRIFT_EXIT: i32 = 2;
+
+const ROLE_EXTENSIONS: [&str; 3] = ["yaml", "yml", "json"];
+
+pub fn command_role_list(cfg: &AppConfig) {
+    let dir = roles_dir(cfg);
+    let mut roles: Vec<String> = match fs::read_dir(&dir) {
+        Err(_) => Vec::new(),
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| ROLE_EXTENSIONS.contains(&e.to_string_lossy().as_ref())))
+            .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+            .collect(),
+    };
+    roles.sort();
+    println!("Roles are stored in {}", dir.display());
+    for role in roles {
+        let assigned = cfg.role_assignments.values().filter(|r| **r == role).count();
+        println!("  {role} ({assigned} user(s) assigned)");
+    }
+}
+
+pub fn command_role_apply(mut cfg: AppConfig, role: &str, selector: &UserSelector, yes: bool, users_endpoint: &str, user_policy_endpoint: &str) {
+    let template = exit_on_error(load_role(&cfg, role));
+    let users = select_confirmed_users(&cfg, selector, yes, &format!("apply role \"{role}\" to"), users_endpoint);
+    let mut failed = 0;
+    for user in users {
+        let mut values = policy_to_map(&user.policy);
+        let drift = exit_on_error(role_drift(&values, &template));
+        if drift.is_empty() {
+            println!("{} already matches role \"{role}\".", user.name);
+        } else {
+            for (key, _, desired) in &drift {
+                values.insert(key.clone(), desired.clone());
+            }
+            let result = serde_json::from_value::<Policy>(Value::Object(values))
+                .map_err(|e| e.to_string())
+                .and_then(|policy| {
+                    UserList::update_user_policy(UserList::new(user_policy_endpoint, &cfg.server_url, &cfg.api_key), &policy, &user.id)
+                        .map_err(|e| e.to_string())
+                });
+            if let Err(e) = result {
+                eprintln!("[ERROR] Unable to apply role \"{role}\" to {}.  {e}", user.name);
+                failed += 1;
+                continue;
+            }
+            println!("Role \"{role}\" applied to {}.", user.name);
+            for (key, current, desired) in &drift {
+                println!("  {key}: {current} -> {desired}");
+            }
+        }
+        cfg.role_assignments.insert(user.name.clone(), role.to_owned());
+    }
+    store_config(&cfg);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+pub fn command_role_diff(cfg: &AppConfig, role: &str, usernames: &[String], pattern: Option<&str>, users_endpoint: &str) {
+    let template = exit_on_error(load_role(cfg, role));
+    let users = if usernames.is_empty() && pattern.is_none() {
+        // Without a selection, check every user the role was applied to.
+        let assigned: Vec<String> = cfg
+            .role_assignments
+            .iter()
+            .filter(|(_, r)| *r == role)
+            .map(|(user, _)| user.clone())
+            .collect();
+        if assigned.is_empty() {
+            println!("No users are assigned to role \"{role}\".");
+            return;
+        }
+        exit_on_error(select_users(cfg, &UserSelector::by_name(assigned, None), users_endpoint))
+    } else {
+        exit_on_error(select_users(cfg, &UserSelector::by_name(usernames.to_vec(), pattern.map(str::to_owned)), users_endpoint))
+    };
+
+    let mut drifted = false;
+    for user in users {
+        let drift = exit_on_error(role_drift(&policy_to_map(&user.policy), &template));
+        if drift.is_empty() {
+            println!("{} matches role \"{role}\".", user.name);
+        } else {
+            drifted = true;
+            println!("{} has drifted from role \"{role}\":", user.name);
+            for (key, current, desired) in &drift {
+                println!("  {key}: {current} (role: {desired})");
+            }
+        }
+    }
+    if drifted {
+        std::process::exit(ROLE_DRIFT_EXIT);
+    }
+}
+
+/*
+    The following section contains additional
+    functions that are used to support the role_commands
+    base functions.
+*/
+
+///
+/// Roles are kept in a "roles" directory next to the configuration file.
+///
+fn roles_dir(cfg: &AppConfig) -> PathBuf {
+    cfg.config_path.with_file_name("roles")
+}
+
+///
+/// Reads a role template, a partial user policy in YAML or JSON keyed by the Jellyfin policy names.
+///
+fn load_role(cfg: &AppConfig, role: &str) -> Result<Map<String, Value>, String> {
+    // The name becomes part of a path, so it must not be able to leave the roles directory.
+    if role.is_empty() || role.contains(['/', '\\', '.']) {
+        return Err("Role names cannot be empty or contain '/', '\\' or '.'.".to_owned());
+    }
+    let dir = roles_dir(cfg);
+    let Some(path) = ROLE_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{role}.{ext}")))
+        .find(|p| p.exists())
+    else {
+        return Err(format!("Role \"{role}\" not found.  Expected {}.", dir.join(format!("{role}.yaml")).display()));
+    };
+    let data = fs::read_to_string(&path).map_err(|e| format!("Unable to read {}, {e}", path.display()))?;
+    // YAML is a superset of JSON, so both formats are read the same way.
+    match serde_norway::from_str::<Value>(&data) {
+        Ok(Value::Object(map)) => Ok(map),
+        Ok(_) => Err(format!("Role \"{role}\" must contain policy keys and values.")),
+        Err(e) => Err(format!("Unable to parse {}, {e}", path.display())),
+    }
+}
+
+///
+/// Lists the policy values that differ from the role as (key, current, role value).  Role keys are
+/// validated against the user policy so typos are reported rather than ignored.
+///
+fn role_drift(values: &Map<String, Value>, template: &Map<String, Value>) -> Result<Vec<(String, Value, Value)>, String> {
+    let mut drift = Vec::new();
+    for (key, desired) in template {
+        let (name, current) = policy_entry(values, key)?;
+        let same_type = matches!(
+            (current, desired),
+            (Value::Bool(_), Value::Bool(_))
+                | (Value::Number(_) | Value::Null, Value::Number(_) | Value::Null)
+                | (Value::String(_), Value::String(_))
+                | (Value::Array(_), Value::Array(_))
+        );
+        if !same_type {
+            return Err(format!("Role value for \"{name}\" should look like {current}, found {desired}."));
+        }
+        if current != desired {
+            drift.push((name.to_owned(), current.clone(), desired.clone()));
+        }
+    }
+    Ok(drift)
+}
@@ -1,9 +1,12 @@
 use std::env;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use reqwest::StatusCode;
 use prop_reader::PropReader;
 
-use crate::{ entities::{backup_details::BackupDetails, device_details::DeviceDetails, package_details::PackageDetails, plugin_details::PluginDetails, repository_details::RepositoryDetails, server_info::ServerInfo, task_details::TaskDetails}, plugin_actions::PluginInfo, responder::{simple_get, simple_post}, system_actions::{ execute_task_by_id, get_backups_info, get_devices, get_packages_info, get_repo_info, get_scheduled_tasks, get_taskid_by_taskname, install_package, set_repo_info }, user_actions::{ UserAuth, UserWithPass }, utils::status_handler::{handle_others, handle_unauthorized}, AppConfig, OutputFormat};
+use crate::{ entities::{backup_details::BackupDetails, device_details::DeviceDetails, package_details::PackageDetails, plugin_details::PluginDetails, repository_details::RepositoryDetails, server_info::ServerInfo, task_details::TaskDetails, token_details::TokenItem}, plugin_actions::PluginInfo, responder::{simple_get, simple_post}, system_actions::{ create_api_key, execute_task_by_id, get_api_keys, get_backups_info, get_devices, get_packages_info, get_repo_info, get_scheduled_tasks, get_taskid_by_taskname, install_package, revoke_api_key, set_repo_info }, user_actions::{ QuickConnectState, UserAuth, UserAuthQuickconnect, UserList, UserWithPass }, utils::{config::store_config, status_handler::{handle_others, handle_unauthorized}}, AppConfig, OutputFormat};
 
 
 pub fn command_initialize(mut cfg: AppConfig, username: &str, password: String, server_url: &str) {
@@ -15,6 +18,123 @@ pub fn command_initialize(mut cfg: AppConfig, username: &str, password: String,
             token_to_api(cfg);
 }
 
+/// Exit code used when the QuickConnect code is not approved before the timeout.
+const QUICKCONNECT_TIMEOUT_EXIT: i32 = 2;
+/// Exit code used when the server expires or rejects the QuickConnect request.
+const QUICKCONNECT_EXPIRED_EXIT: i32 = 3;
+/// Exit code used when QuickConnect is disabled on the server.
+const QUICKCONNECT_DISABLED_EXIT: i32 = 4;
+
+pub fn command_quickconnect(mut cfg: AppConfig, server_url: Option<String>, timeout: u64) {
+    println!("[INFO] QuickConnect auth in progress...");
+    let server_url = server_url.unwrap_or_else(|| {
+        print!("[INPUT] Please enter your Jellyfin URL:  ");
+        io::stdout().flush().expect("Unable to get Jellyfin URL.");
+        let mut server_url_input = String::new();
+        io::stdin()
+            .read_line(&mut server_url_input)
+            .expect("Could not read server url information");
+        server_url_input
+    });
+    env::consts::OS.clone_into(&mut cfg.os);
+    server_url.replace("\'","").replace("\"","").trim().clone_into(&mut cfg.server_url);
+
+    println!("[INFO] Attempting to initialize a QuickConnect request.....");
+    let details = match UserAuthQuickconnect::quickconnect_initiate(UserAuthQuickconnect::new(&cfg.server_url)) {
+        Err(e) => {
+            eprintln!("[ERROR] Unable to initiate QuickConnect request, {e}");
+            std::process::exit(1);
+        }
+        Ok(None) => {
+            eprintln!("[ERROR] QuickConnect is not enabled on this server.");
+            std::process::exit(QUICKCONNECT_DISABLED_EXIT);
+        }
+        Ok(Some(i)) => i,
+    };
+    println!("Your login code is: {}", details.code);
+
+    // Wait while the QuickConnect code is approved.
+    let deadline = Instant::now() + Duration::from_secs(timeout);
+    loop {
+        let state = match UserAuthQuickconnect::quickconnect_get_status(&details, &cfg.server_url) {
+            Err(e) => {
+                eprintln!("\n[ERROR] Unable to check QuickConnect status, {e}");
+                std::process::exit(1);
+            }
+            Ok(i) => i,
+        };
+        match state {
+            QuickConnectState::Authenticated => {
+                println!();
+                break;
+            }
+            QuickConnectState::Expired => {
+                eprintln!("\n[ERROR] QuickConnect request expired or was denied.");
+                std::process::exit(QUICKCONNECT_EXPIRED_EXIT);
+            }
+            QuickConnectState::Pending => {}
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now()).as_secs();
+        if remaining == 0 {
+            eprintln!("\n[ERROR] Timed out waiting for QuickConnect approval.");
+            std::process::exit(QUICKCONNECT_TIMEOUT_EXIT);
+        }
+        print!("\r[INFO] Waiting for approval... {}:{:02} remaining ", remaining / 60, remaining % 60);
+        io::stdout().flush().ok();
+        thread::sleep(Duration::from_secs(1));
+    }
+
+    // Now that we are authenticated we need to tie our QuickConnect to the account
+    cfg.api_key = match UserAuthQuickconnect::quickconnect_authenticate(&details, &cfg.server_url) {
+        Err(e) => {
+            eprintln!("[ERROR] Unable to authenticate with QuickConnect, {e}");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    };
+    "configured".clone_into(&mut cfg.status);
+    token_to_api(cfg);
+}
+
+pub fn command_quickconnect_authorize(cfg: &AppConfig, code: &str, username: &str, users_endpoint: &str) {
+    require_configured(cfg);
+    let user_id = UserList::get_user_id(
+        UserList::new(users_endpoint, &cfg.server_url, &cfg.api_key),
+        username,
+    );
+    match UserAuthQuickconnect::quickconnect_authorize(
+        UserAuthQuickconnect::new(&cfg.server_url),
+        &cfg.api_key,
+        code,
+        &user_id,
+    ) {
+        Err(e) => {
+            eprintln!("Unable to authorize QuickConnect code, {e}");
+            std::process::exit(1);
+        }
+        Ok(true) => println!("QuickConnect code {code} authorized for {username}."),
+        Ok(false) => {
+            eprintln!("QuickConnect code {code} was not found or has expired.");
+            std::process::exit(QUICKCONNECT_EXPIRED_EXIT);
+        }
+    }
+}
+
+pub fn command_quickconnect_status(cfg: &AppConfig) {
+    require_configured(cfg);
+    match UserAuthQuickconnect::quickconnect_enabled(UserAuthQuickconnect::new(&cfg.server_url)) {
+        Err(e) => {
+            eprintln!("Unable to get QuickConnect status, {e}");
+            std::process::exit(1);
+        }
+        Ok(true) => println!("QuickConnect is enabled."),
+        Ok(false) => {
+            println!("QuickConnect is disabled.");
+            std::process::exit(QUICKCONNECT_DISABLED_EXIT);
+        }
+    }
+}
+
 pub fn command_get_devices(cfg: &AppConfig, active: bool, output_format: &OutputFormat, devices_endpoint: &str) {
     let devices: Vec<DeviceDetails> = match get_devices(
         ServerInfo::new(devices_endpoint, &cfg.server_url, &cfg.api_key),
@@ -257,6 +377,80 @@ pub fn command_get_backups(cfg: &AppConfig, output_format: &OutputFormat, backup
     }
     
 }
+pub fn command_list_api_keys(cfg: &AppConfig, output_format: &OutputFormat, api_keys_endpoint: &str) {
+    let keys = list_api_keys(cfg, api_keys_endpoint);
+
+    match output_format {
+        OutputFormat::Json => {
+            TokenItem::json_print(&keys);
+        }
+        OutputFormat::Csv => {
+            TokenItem::csv_print(&keys);
+        }
+        OutputFormat::Table => {
+            TokenItem::table_print(keys, &cfg.api_key);
+        }
+    }
+}
+
+pub fn command_create_api_key(cfg: &AppConfig, app: &str, api_keys_endpoint: &str) {
+    let before = list_api_keys(cfg, api_keys_endpoint);
+    create_api_key(&ServerInfo::new(api_keys_endpoint, &cfg.server_url, &cfg.api_key), app);
+    // Jellyfin does not return the new key, so find it by comparing against the keys that existed before.
+    for key in list_api_keys(cfg, api_keys_endpoint) {
+        if !before.iter().any(|k| k.access_token == key.access_token) {
+            println!("{}", key.access_token);
+        }
+    }
+}
+
+pub fn command_revoke_api_key(cfg: &AppConfig, key: &str, api_keys_endpoint: &str) {
+    let keys = list_api_keys(cfg, api_keys_endpoint);
+    // Treat the argument as a token first and fall back to matching the application name.
+    let mut matched: Vec<&TokenItem> = keys.iter().filter(|k| k.access_token == key).collect();
+    if matched.is_empty() {
+        matched = keys.iter().filter(|k| k.app_name.eq_ignore_ascii_case(key)).collect();
+    }
+    if matched.is_empty() {
+        eprintln!("No API key matches \"{key}\".");
+        std::process::exit(1);
+    }
+    if matched.iter().any(|k| k.access_token == cfg.api_key) {
+        eprintln!("Refusing to revoke the API key JellyRoller is currently using.  Use 'api-key rotate' instead.");
+        std::process::exit(1);
+    }
+    for item in matched {
+        revoke_api_key(
+            &ServerInfo::new(api_keys_endpoint, &cfg.server_url, &cfg.api_key),
+            &item.access_token,
+        )
+        .expect("Unable to revoke the specified API key.");
+    }
+}
+
+pub fn command_rotate_api_key(mut cfg: AppConfig, api_keys_endpoint: &str) {
+    let old_key = cfg.api_key.clone();
+    let before = list_api_keys(&cfg, api_keys_endpoint);
+    create_api_key(&ServerInfo::new(api_keys_endpoint, &cfg.server_url, &cfg.api_key), "JellyRoller");
+    let new_key = match list_api_keys(&cfg, api_keys_endpoint)
+        .into_iter()
+        .find(|k| !before.iter().any(|b| b.access_token == k.access_token))
+    {
+        None => {
+            eprintln!("Unable to locate the newly created API key.  The existing key has not been changed.");
+            std::process::exit(1);
+        }
+        Some(k) => k.access_token,
+    };
+
+    // Persist the new key before revoking the old one so a failure never leaves the configuration without a valid key.
+    cfg.api_key = new_key;
+    store_config(&cfg);
+    println!("Configuration updated to use the new API key.");
+    revoke_api_key(&ServerInfo::new(api_keys_endpoint, &cfg.server_url, &cfg.api_key), &old_key)
+        .expect("Unable to revoke the previous API key.");
+}
+
 /// All of the following calls are POST
 /// 
 /// Call /Startup/Configuration with JSON body of:
@@ -382,7 +576,39 @@ pub fn command_server_setup(mut server_url: String, filename: String) {
     base functions.
 */
 
+///
+/// QuickConnect commands skip the start-up configuration check, so confirm a server is configured before using it.
+///
+fn require_configured(cfg: &AppConfig) {
+    if cfg.status != "configured" {
+        eprintln!("Application is not configured!  Run 'jellyroller reconfigure' first.");
+        std::process::exit(1);
+    }
+}
+
+///
+/// Retrieves every API key registered on the server.
+///
+fn list_api_keys(cfg: &AppConfig, api_keys_endpoint: &str) -> Vec<TokenItem> {
+    match get_api_keys(ServerInfo::new(api_keys_endpoint, &cfg.server_url, &cfg.api_key)) {
+        Err(e) => {
+            eprintln!("Unable to get API keys, {e}");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    }
+}
+
 pub fn token_to_api(mut cfg: AppConfig) {
+    convert_token_to_api_key(&mut cfg);
+    store_config(&cfg);
+    println!("[INFO] Auth token successfully converted to API key.");
+}
+
+///
+/// Swaps the user auth token held in the configuration for a JellyRoller API key, creating the key if needed.
+///
+pub fn convert_token_to_api_key(cfg: &mut AppConfig) {
     println!("[INFO] Attempting to auto convert user auth token to API key.....");
     // Check if api key already exists
     if UserWithPass::retrieve_api_token(UserWithPass::new(
@@ -408,11 +634,8 @@ pub fn token_to_api(mut cfg: AppConfig) {
         None,
         None,
         format!("{}/Auth/Keys", cfg.server_url),
-        cfg.api_key,
+        cfg.api_key.clone(),
     ))
     .unwrap();
     cfg.token = "apiKey".to_string();
-    confy::store("jellyroller", "jellyroller", cfg)
-        .expect("[ERROR] Unable to store updated configuration.");
-    println!("[INFO] Auth token successfully converted to API key.");
 }
\ No newline at end of file
@@ -1,12 +1,81 @@
-use std::io::{BufRead, BufReader};
-use std::fs::{self, File};
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use chrono::{DateTime, NaiveDate, NaiveTime, SecondsFormat, Utc};
+use regex::Regex;
+use serde_json::{Map, Value};
 use crate::{AppConfig, 
     ImageType,
+    OutputFormat,
+    PasswordGenerationArgs,
+    ScheduleTargets,
+    ScheduleWindow,
+    UserSelector,
+    utils::credentials::{Credential, export_credentials, generate_password},
     utils::output_writer::export_data, 
-    utils::common::image_to_base64,
-    system_actions::{remove_device, get_deviceid_by_username, update_image}, 
+    utils::config::store_config,
+    utils::common::{exit_on_error, image_to_base64, parse_jellyfin_date},
+    utils::password_input::prompt_password,
+    system_actions::{get_libraries, remove_device, get_deviceid_by_username, update_image}, 
     user_actions::{UserList, UserWithPass}, 
-    entities::{user_details::UserDetails, server_info::ServerInfo}};
+    entities::{library_details::LibraryDetails, lockout_details::LockoutStatus, prune_journal::{PruneAction, PruneEntry, PruneJournal}, user_details::{AccessSchedule, Configuration, Policy, UserDetails}, user_import::UserImport, user_manifest::{ManifestUser, UserManifest}, server_info::ServerInfo}};
+
+// Differences between a user manifest and the server.
+struct UserPlan {
+    changes: Vec<PlannedChange>,
+    // Users on the server that are not in the manifest and are not being deleted.
+    ignored: usize,
+    // Administrators that are not in the manifest and are kept although unmanaged users are deleted.
+    kept_admins: usize,
+    libraries: Vec<LibraryDetails>,
+}
+
+/// What `users plan` and `users apply` do with users on the server that are not in the manifest.
+#[derive(Clone, Copy, PartialEq)]
+pub enum UnmanagedUsers {
+    Keep,
+    /// Delete them, except for administrators.
+    Delete,
+    DeleteIncludingAdmins,
+}
+
+impl UnmanagedUsers {
+    pub fn from_flags(delete_unmanaged: bool, include_admins: bool) -> UnmanagedUsers {
+        match (delete_unmanaged, include_admins) {
+            (false, _) => UnmanagedUsers::Keep,
+            (true, false) => UnmanagedUsers::Delete,
+            (true, true) => UnmanagedUsers::DeleteIncludingAdmins,
+        }
+    }
+}
+
+enum PlannedChange {
+    Create(Box<ManifestUser>),
+    Update {
+        name: String,
+        id: String,
+        policy: Box<Policy>,
+        differences: Vec<String>,
+    },
+    Delete {
+        name: String,
+        id: String,
+    },
+}
+
+// State shared across the rows of an add-users import.
+struct UserImportContext<'a> {
+    cfg: &'a AppConfig,
+    generation: Option<&'a PasswordGenerationArgs>,
+    users_endpoint: &'a str,
+    user_policy_endpoint: &'a str,
+    // Libraries are only fetched once, and only when a row restricts library access.
+    libraries: Option<Vec<LibraryDetails>>,
+    credentials: Vec<Credential>,
+}
+
+// Subtitle modes accepted by Jellyfin for the SubtitleMode preference.
+const SUBTITLE_MODES: [&str; 5] = ["Default", "Always", "OnlyForced", "None", "Smart"];
 
 #[derive(Clone, Debug, PartialEq)]
 enum UserMods {
@@ -14,22 +83,19 @@ enum UserMods {
     Active
 }
 
-pub fn command_delete_user(cfg: AppConfig, username: String) {
-    let user_id = get_user_id(&cfg, &username);
-    let server_path = format!("{}/Users/{user_id}", cfg.server_url);
-    match UserWithPass::delete_user(UserWithPass::new(
-        Some(username),
-        None,
-        None,
-        server_path,
-        cfg.api_key,
-    )) {
-        Err(_) => {
-            eprintln!("Unable to delete user.");
-            std::process::exit(1);
-        }
-        Ok(i) => i,
-    }
+pub fn command_delete_user(cfg: &AppConfig, selector: &UserSelector, yes: bool, users_endpoint: &str) {
+    let users = select_confirmed_users(cfg, selector, yes, "delete", users_endpoint);
+    exit_on_error(keep_an_administrator(cfg, &users, users_endpoint));
+    run_for_users(&users, "deleted", |user| {
+        UserWithPass::delete_user(UserWithPass::new(
+            Some(user.name.clone()),
+            None,
+            None,
+            format!("{}/Users/{}", cfg.server_url, user.id),
+            cfg.api_key.clone(),
+        ))
+        .map_err(|e| e.to_string())
+    });
 }
 
 pub fn command_list_users(cfg: &AppConfig, export: bool, mut output: String, username: &str, users_endpoint: &str, user_id_endpoint: &str) {
@@ -107,37 +173,628 @@ pub fn command_reset_password(cfg: AppConfig, username: &str, password: String,
     }
 }
 
-pub fn command_disable_user(cfg: &AppConfig, username: &str, user_policy_endpoint: &str, user_id_endpoint: &str) {
-    modify_user(cfg, username, user_policy_endpoint, user_id_endpoint, &UserMods::Active, true);
+pub fn command_disable_user(cfg: &AppConfig, selector: &UserSelector, yes: bool, users_endpoint: &str, user_policy_endpoint: &str) {
+    let users = select_confirmed_users(cfg, selector, yes, "disable", users_endpoint);
+    exit_on_error(keep_an_administrator(cfg, &users, users_endpoint));
+    modify_users(cfg, &users, user_policy_endpoint, &UserMods::Active, true);
 }
 
-pub fn command_enable_user(cfg: &AppConfig, username: &str, user_policy_endpoint: &str, user_id_endpoint: &str) {
-    modify_user(cfg, username, user_policy_endpoint, user_id_endpoint, &UserMods::Active, false);
+pub fn command_enable_user(cfg: &AppConfig, selector: &UserSelector, users_endpoint: &str, user_policy_endpoint: &str) {
+    let users = exit_on_error(select_users(cfg, selector, users_endpoint));
+    modify_users(cfg, &users, user_policy_endpoint, &UserMods::Active, false);
 }
 
-pub fn command_grant_admin(cfg: &AppConfig, username: &str, user_policy_endpoint: &str, user_id_endpoint: &str) {
-    modify_user(cfg, username, user_policy_endpoint, user_id_endpoint, &UserMods::Admin, true);
+pub fn command_grant_admin(cfg: &AppConfig, selector: &UserSelector, yes: bool, users_endpoint: &str, user_policy_endpoint: &str) {
+    let users = select_confirmed_users(cfg, selector, yes, "grant admin rights to", users_endpoint);
+    modify_users(cfg, &users, user_policy_endpoint, &UserMods::Admin, true);
 }
 
-pub fn command_revoke_admin(cfg: &AppConfig, username: &str, user_policy_endpoint: &str, user_id_endpoint: &str) {
-    modify_user(cfg, username, user_policy_endpoint, user_id_endpoint, &UserMods::Admin, false);
+pub fn command_revoke_admin(cfg: &AppConfig, selector: &UserSelector, yes: bool, users_endpoint: &str, user_policy_endpoint: &str) {
+    let users = select_confirmed_users(cfg, selector, yes, "revoke admin rights from", users_endpoint);
+    exit_on_error(keep_an_administrator(cfg, &users, users_endpoint));
+    modify_users(cfg, &users, user_policy_endpoint, &UserMods::Admin, false);
 }
 
 pub fn command_add_user(cfg: &AppConfig, username: String, password: String) {
     add_user(cfg, username, password);
 }
 
-pub fn command_add_users(cfg: &AppConfig, inputfile: String) {
-    let reader = BufReader::new(File::open(inputfile).unwrap());
-    for line in reader.lines() {
-        match line {
-            Ok(l) => {
-                let vec: Vec<&str> = l.split(',').collect();
-                add_user(cfg, vec[0].to_owned(), vec[1].to_owned());
+pub fn command_add_user_generated(cfg: &AppConfig, username: String, options: &PasswordGenerationArgs) {
+    let password = generate_password(options);
+    add_user(cfg, username.clone(), password.clone());
+    export_credentials(&[Credential { username, password }], options);
+}
+
+pub fn command_add_users(
+    cfg: &AppConfig,
+    inputfile: &str,
+    generation: Option<&PasswordGenerationArgs>,
+    continue_on_error: bool,
+    users_endpoint: &str,
+    user_policy_endpoint: &str,
+) {
+    let rows = match UserImport::read_file(inputfile) {
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    };
+    let total = rows.len();
+    let mut import = UserImportContext {
+        cfg,
+        generation,
+        users_endpoint,
+        user_policy_endpoint,
+        libraries: None,
+        credentials: Vec::new(),
+    };
+    let mut created = 0;
+    let mut failed = 0;
+    for (index, row) in rows.into_iter().enumerate() {
+        let result = row.and_then(|user| import_user(&mut import, user));
+        match result {
+            Ok(()) => created += 1,
+            Err(e) => {
+                failed += 1;
+                eprintln!("[ERROR] Row {}: {e}", index + 1);
+                if !continue_on_error {
+                    break;
+                }
+            }
+        }
+    }
+    // Hand out the passwords of every user that was created, even when the import stopped early.
+    if let Some(options) = generation {
+        export_credentials(&import.credentials, options);
+    }
+    println!("{created} user(s) created, {failed} failed, {} skipped.", total - created - failed);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+pub fn command_users_plan(cfg: &AppConfig, manifest_file: &str, unmanaged: UnmanagedUsers, users_endpoint: &str) {
+    let manifest = read_manifest(manifest_file);
+    let plan = match plan_users(cfg, &manifest, unmanaged, users_endpoint) {
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    };
+    print_plan(&plan);
+}
+
+pub fn command_users_apply(
+    cfg: &AppConfig,
+    manifest_file: &str,
+    unmanaged: UnmanagedUsers,
+    auto_approve: bool,
+    generation: &PasswordGenerationArgs,
+    users_endpoint: &str,
+    user_policy_endpoint: &str,
+) {
+    let manifest = read_manifest(manifest_file);
+    let plan = match plan_users(cfg, &manifest, unmanaged, users_endpoint) {
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    };
+    print_plan(&plan);
+    if plan.changes.is_empty() {
+        return;
+    }
+    if !auto_approve && !confirm("Do you want to apply these changes?") {
+        println!("Apply cancelled.");
+        std::process::exit(1);
+    }
+
+    let mut credentials: Vec<Credential> = Vec::new();
+    let mut applied = 0;
+    let mut failed = 0;
+    for change in plan.changes {
+        let result = match change {
+            PlannedChange::Create(user) => {
+                let password = match &user.password {
+                    Some(p) => p.clone(),
+                    None => {
+                        let p = generate_password(generation);
+                        credentials.push(Credential { username: user.name.clone(), password: p.clone() });
+                        p
+                    }
+                };
+                create_manifest_user(cfg, &user, password, &plan.libraries, users_endpoint, user_policy_endpoint)
+            }
+            PlannedChange::Update { name, id, policy, .. } => {
+                UserList::update_user_policy(UserList::new(user_policy_endpoint, &cfg.server_url, &cfg.api_key), &policy, &id)
+                    .map(|()| println!("User \"{name}\" updated."))
+                    .map_err(|e| format!("Unable to update user \"{name}\".  {e}"))
+            }
+            PlannedChange::Delete { name, id } => UserWithPass::delete_user(UserWithPass::new(
+                Some(name.clone()),
+                None,
+                None,
+                format!("{}/Users/{id}", cfg.server_url),
+                cfg.api_key.clone(),
+            ))
+            .map_err(|e| format!("Unable to delete user \"{name}\".  {e}")),
+        };
+        match result {
+            Ok(()) => applied += 1,
+            Err(e) => {
+                failed += 1;
+                eprintln!("[ERROR] {e}");
+            }
+        }
+    }
+    export_credentials(&credentials, generation);
+    println!("Apply complete: {applied} change(s) applied, {failed} failed.");
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+pub fn command_users_prune(cfg: &AppConfig, inactive_days: i64, delete: bool, exclude: &[String], yes: bool, users_endpoint: &str, user_policy_endpoint: &str) {
+    let excluded: Vec<Regex> = exit_on_error(exclude.iter().map(|p| glob_to_regex(p)).collect());
+    let users = match UserList::list_users(UserList::new(users_endpoint, &cfg.server_url, &cfg.api_key)) {
+        Err(e) => {
+            eprintln!("Unable to gather users.  {e}");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    };
+
+    let now = Utc::now();
+    let mut admins = 0;
+    let mut candidates: Vec<(UserDetails, Option<DateTime<Utc>>)> = Vec::new();
+    for user in users {
+        if excluded.iter().any(|r| r.is_match(&user.name)) || (!delete && user.policy.is_disabled) {
+            continue;
+        }
+        let last_active = [
+            parse_jellyfin_date(user.last_activity_date.as_deref()),
+            parse_jellyfin_date(user.last_login_date.as_deref()),
+        ]
+        .into_iter()
+        .flatten()
+        .max();
+        if last_active.is_some_and(|date| (now - date).num_days() < inactive_days) {
+            continue;
+        }
+        // Pruning must never be able to lock everyone out of the server.
+        if user.policy.is_administrator {
+            admins += 1;
+            continue;
+        }
+        candidates.push((user, last_active));
+    }
+
+    if admins > 0 {
+        println!("Skipping {admins} inactive administrator(s).");
+    }
+    if candidates.is_empty() {
+        println!("No users to prune.");
+        return;
+    }
+    let (action, verb) = if delete { (PruneAction::Deleted, "deleted") } else { (PruneAction::Disabled, "disabled") };
+    println!("The following users have been inactive for {inactive_days} days or more and will be {verb}:\n");
+    for (user, last_active) in &candidates {
+        println!("  {} (last active: {})", user.name, last_active.map_or_else(|| "never".to_owned(), |d| d.format("%Y-%m-%d").to_string()));
+    }
+    if !yes && !confirm(&format!("Do you want to prune {} user(s)?", candidates.len())) {
+        println!("Prune cancelled.");
+        std::process::exit(1);
+    }
+
+    let path = journal_path(cfg);
+    let mut journal = exit_on_error(PruneJournal::load(&path));
+    let mut failed = 0;
+    for (mut user, last_active) in candidates {
+        let result = if delete {
+            UserWithPass::delete_user(UserWithPass::new(
+                Some(user.name.clone()),
+                None,
+                None,
+                format!("{}/Users/{}", cfg.server_url, user.id),
+                cfg.api_key.clone(),
+            ))
+        } else {
+            user.policy.is_disabled = true;
+            UserList::update_user_policy(UserList::new(user_policy_endpoint, &cfg.server_url, &cfg.api_key), &user.policy, &user.id)
+        };
+        if let Err(e) = result {
+            eprintln!("[ERROR] Unable to prune {}.  {e}", user.name);
+            failed += 1;
+            continue;
+        }
+        println!("{} {verb}.", user.name);
+        journal.entries.push(PruneEntry {
+            date: now.to_rfc3339_opts(SecondsFormat::Secs, true),
+            action,
+            username: user.name,
+            user_id: user.id,
+            last_activity: last_active.map_or_else(|| "never".to_owned(), |d| d.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            restored: false,
+        });
+        // Saved as we go so that users already pruned can be restored even if a later one fails.
+        exit_on_error(journal.save(&path));
+    }
+    println!("Prune complete, recorded in {}", path.display());
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+pub fn command_users_restore(cfg: &AppConfig, usernames: &[String], all: bool, users_endpoint: &str, user_policy_endpoint: &str) {
+    let path = journal_path(cfg);
+    let mut journal = exit_on_error(PruneJournal::load(&path));
+    if !all && usernames.is_empty() {
+        // Nothing selected, so show what could be restored.
+        let pending: Vec<&PruneEntry> = journal.entries.iter().filter(|e| e.action == PruneAction::Disabled && !e.restored).collect();
+        if pending.is_empty() {
+            println!("No pruned users can be restored.");
+        }
+        for entry in pending {
+            println!("  {} (disabled {}, last active: {})", entry.username, entry.date, entry.last_activity);
+        }
+        return;
+    }
+    let restorable: Vec<usize> = journal
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.action == PruneAction::Disabled && !e.restored)
+        .filter(|(_, e)| all || usernames.iter().any(|u| u.eq_ignore_ascii_case(&e.username)))
+        .map(|(i, _)| i)
+        .collect();
+
+    for username in usernames {
+        if !restorable.iter().any(|i| journal.entries[*i].username.eq_ignore_ascii_case(username)) {
+            eprintln!("[ERROR] {username} was not disabled by users prune.");
+        }
+    }
+
+    let users = match UserList::list_users(UserList::new(users_endpoint, &cfg.server_url, &cfg.api_key)) {
+        Err(e) => {
+            eprintln!("Unable to gather users.  {e}");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    };
+    let mut failed = restorable.len() < usernames.len();
+    for index in restorable {
+        let entry = &mut journal.entries[index];
+        // Look the user up by id in case they were renamed since being pruned.
+        let result = match users.iter().find(|u| u.id == entry.user_id) {
+            None => Err("The user no longer exists.".to_owned()),
+            Some(user) => {
+                let mut policy = user.policy.clone();
+                policy.is_disabled = false;
+                UserList::update_user_policy(UserList::new(user_policy_endpoint, &cfg.server_url, &cfg.api_key), &policy, &user.id)
+                    .map_err(|e| e.to_string())
+            }
+        };
+        match result {
+            Ok(()) => {
+                entry.restored = true;
+                println!("{} enabled.", entry.username);
+            }
+            Err(e) => {
+                eprintln!("[ERROR] Unable to restore {}.  {e}", entry.username);
+                failed = true;
             }
-            Err(e) => println!("Unable to add user.  {e}"),
         }
     }
+    exit_on_error(journal.save(&path));
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+pub fn command_users_locked(cfg: &AppConfig, output_format: &OutputFormat, users_endpoint: &str) {
+    let users = match UserList::list_users(UserList::new(users_endpoint, &cfg.server_url, &cfg.api_key)) {
+        Err(e) => {
+            eprintln!("Unable to gather users.  {e}");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    };
+    let rows: Vec<LockoutStatus> = users
+        .iter()
+        .filter_map(|user| {
+            let policy = &user.policy;
+            let threshold = policy.lockout_threshold();
+            let status = if policy.is_locked_out() {
+                "locked"
+            } else if policy.invalid_login_attempt_count > 0 && threshold.is_some_and(|n| n - policy.invalid_login_attempt_count <= 1) {
+                // One more failed login will lock the account.
+                "near lockout"
+            } else {
+                return None;
+            };
+            Some(LockoutStatus {
+                username: user.name.clone(),
+                status: status.to_owned(),
+                failed_attempts: policy.invalid_login_attempt_count,
+                lockout_threshold: threshold,
+                disabled: policy.is_disabled,
+            })
+        })
+        .collect();
+
+    if rows.is_empty() && matches!(output_format, OutputFormat::Table) {
+        println!("No users are locked out or close to lockout.");
+        return;
+    }
+    match output_format {
+        OutputFormat::Json => LockoutStatus::json_print(&rows),
+        OutputFormat::Csv => LockoutStatus::csv_print(&rows),
+        OutputFormat::Table => LockoutStatus::table_print(rows),
+    }
+}
+
+pub fn command_user_unlock(cfg: &AppConfig, selector: &UserSelector, users_endpoint: &str, user_policy_endpoint: &str) {
+    let (users, not_locked): (Vec<UserDetails>, Vec<UserDetails>) =
+        exit_on_error(select_users(cfg, selector, users_endpoint)).into_iter().partition(|u| u.policy.is_locked_out());
+    // Users disabled for other reasons, for example by "users prune", must stay disabled.
+    for user in &not_locked {
+        println!("Skipping {}, the account is not locked out.", user.name);
+    }
+    if users.is_empty() {
+        println!("No users to unlock.");
+        return;
+    }
+    run_for_users(&users, "unlocked", |user| {
+        let mut policy = user.policy.clone();
+        policy.is_disabled = false;
+        policy.invalid_login_attempt_count = 0;
+        UserList::update_user_policy(UserList::new(user_policy_endpoint, &cfg.server_url, &cfg.api_key), &policy, &user.id)
+            .map_err(|e| e.to_string())
+    });
+}
+
+pub fn command_user_set_lockout(cfg: &AppConfig, selector: &UserSelector, attempts: i64, users_endpoint: &str, user_policy_endpoint: &str) {
+    if attempts < -1 {
+        eprintln!("Login attempts must be -1 (never lock), 0 (server default) or a positive number.");
+        std::process::exit(1);
+    }
+    let users = exit_on_error(select_users(cfg, selector, users_endpoint));
+    run_for_users(&users, "successfully updated", |user| {
+        let mut policy = user.policy.clone();
+        policy.login_attempts_before_lockout = attempts;
+        UserList::update_user_policy(UserList::new(user_policy_endpoint, &cfg.server_url, &cfg.api_key), &policy, &user.id)
+            .map_err(|e| e.to_string())
+    });
+}
+
+pub fn command_user_access_show(cfg: &AppConfig, username: &str, users_endpoint: &str) {
+    let user = exit_on_error(find_user(cfg, users_endpoint, username));
+    let libraries = exit_on_error(fetch_libraries(cfg));
+    if user.policy.enable_all_folders {
+        println!("{} has access to all libraries.", user.name);
+    } else if user.policy.enabled_folders.is_empty() {
+        println!("{} has no library access.", user.name);
+    } else {
+        println!("{} has access to:", user.name);
+        for name in library_names(&user.policy, &libraries) {
+            println!("  {name}");
+        }
+    }
+}
+
+pub fn command_user_access_grant(cfg: &AppConfig, username: &str, names: &[String], all: bool, users_endpoint: &str, user_policy_endpoint: &str) {
+    change_library_access(cfg, username, users_endpoint, user_policy_endpoint, |policy, libraries, username| {
+        if all {
+            policy.enable_all_folders = true;
+            policy.enabled_folders = Vec::new();
+            return Ok(());
+        }
+        if policy.enable_all_folders {
+            return Ok(());
+        }
+        for id in resolve_library_ids(libraries, names, username)? {
+            if !policy.enabled_folders.contains(&id) {
+                policy.enabled_folders.push(id);
+            }
+        }
+        Ok(())
+    });
+}
+
+pub fn command_user_access_revoke(cfg: &AppConfig, username: &str, names: &[String], all: bool, users_endpoint: &str, user_policy_endpoint: &str) {
+    change_library_access(cfg, username, users_endpoint, user_policy_endpoint, |policy, libraries, username| {
+        let revoked = if all { Vec::new() } else { resolve_library_ids(libraries, names, username)? };
+        if policy.enable_all_folders {
+            // Access to all libraries has to be expanded into the explicit list before anything can be removed.
+            policy.enable_all_folders = false;
+            policy.enabled_folders = libraries.iter().map(|l| l.item_id.clone()).collect();
+        }
+        if all {
+            policy.enabled_folders = Vec::new();
+        } else {
+            policy.enabled_folders.retain(|id| !revoked.contains(id));
+        }
+        Ok(())
+    });
+}
+
+pub fn command_user_access_set(cfg: &AppConfig, username: &str, names: &[String], users_endpoint: &str, user_policy_endpoint: &str) {
+    change_library_access(cfg, username, users_endpoint, user_policy_endpoint, |policy, libraries, username| {
+        policy.enabled_folders = resolve_library_ids(libraries, names, username)?;
+        policy.enable_all_folders = false;
+        Ok(())
+    });
+}
+
+pub fn command_user_schedule_list(cfg: &AppConfig, username: &str, users_endpoint: &str) {
+    let user = exit_on_error(find_user(cfg, users_endpoint, username));
+    if user.policy.access_schedules.is_empty() {
+        println!("{} has no access schedules and can sign in at any time.", user.name);
+    } else {
+        println!("{} can sign in during:", user.name);
+        for schedule in &user.policy.access_schedules {
+            println!("  {schedule}");
+        }
+    }
+}
+
+pub fn command_user_schedule_add(cfg: &AppConfig, targets: &ScheduleTargets, window: &ScheduleWindow, users_endpoint: &str, user_policy_endpoint: &str) {
+    change_schedules(cfg, targets, users_endpoint, user_policy_endpoint, |schedules, user_id| {
+        let added = schedule_from_window(window, user_id);
+        added.validate()?;
+        schedules.push(added);
+        AccessSchedule::validate_all(schedules)
+    });
+}
+
+pub fn command_user_schedule_remove(cfg: &AppConfig, targets: &ScheduleTargets, window: &ScheduleWindow, users_endpoint: &str, user_policy_endpoint: &str) {
+    change_schedules(cfg, targets, users_endpoint, user_policy_endpoint, |schedules, user_id| {
+        let removed = schedule_from_window(window, user_id);
+        let count = schedules.len();
+        schedules.retain(|s| {
+            !(s.day_of_week == removed.day_of_week && s.start_hour == removed.start_hour && s.end_hour == removed.end_hour)
+        });
+        if schedules.len() == count {
+            return Err(format!("No schedule {removed} to remove."));
+        }
+        Ok(())
+    });
+}
+
+pub fn command_user_schedule_clear(cfg: &AppConfig, targets: &ScheduleTargets, users_endpoint: &str, user_policy_endpoint: &str) {
+    change_schedules(cfg, targets, users_endpoint, user_policy_endpoint, |schedules, _| {
+        schedules.clear();
+        Ok(())
+    });
+}
+
+pub fn command_user_policy_get(cfg: &AppConfig, username: &str, key: Option<&str>, users_endpoint: &str) {
+    let user = exit_on_error(find_user(cfg, users_endpoint, username));
+    let values = policy_to_map(&user.policy);
+    match key {
+        None => println!("{}", serde_json::to_string_pretty(&values).unwrap()),
+        Some(key) => {
+            let (_, value) = exit_on_error(policy_entry(&values, key));
+            match value {
+                Value::String(s) => println!("{s}"),
+                v => println!("{v}"),
+            }
+        }
+    }
+}
+
+pub fn command_user_policy_set(cfg: &AppConfig, username: &str, settings: &[String], users_endpoint: &str, user_policy_endpoint: &str) {
+    let user = exit_on_error(find_user(cfg, users_endpoint, username));
+    let mut values = policy_to_map(&user.policy);
+    let mut differences = Vec::new();
+    for setting in settings {
+        let Some((key, raw)) = setting.split_once('=') else {
+            eprintln!("Policy settings must be given as Key=Value, found \"{setting}\".");
+            std::process::exit(1);
+        };
+        let (name, current) = exit_on_error(policy_entry(&values, key.trim()));
+        let name = name.to_owned();
+        let new_value = exit_on_error(parse_policy_value(&name, current, raw));
+        if *current != new_value {
+            differences.push(format!("{name}: {current} -> {new_value}"));
+        }
+        values.insert(name, new_value);
+    }
+
+    if differences.is_empty() {
+        println!("No changes.  The policy of {} already has these values.", user.name);
+        return;
+    }
+    let policy: Policy = exit_on_error(
+        serde_json::from_value(Value::Object(values)).map_err(|e| format!("Unable to apply policy change, {e}")),
+    );
+    for difference in &differences {
+        println!("  {difference}");
+    }
+    exit_on_error(
+        UserList::update_user_policy(UserList::new(user_policy_endpoint, &cfg.server_url, &cfg.api_key), &policy, &user.id)
+            .map_err(|e| e.to_string()),
+    );
+    println!("User {} successfully updated.", user.name);
+}
+
+pub fn command_user_rename(mut cfg: AppConfig, username: &str, new_name: &str, users_endpoint: &str, user_id_endpoint: &str) {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        eprintln!("The new username cannot be empty.");
+        std::process::exit(1);
+    }
+    let users = match UserList::list_users(UserList::new(users_endpoint, &cfg.server_url, &cfg.api_key)) {
+        Err(e) => {
+            eprintln!("Unable to gather users.  {e}");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    };
+    let Some(mut user) = users.iter().find(|u| u.name.eq_ignore_ascii_case(username)).cloned() else {
+        eprintln!("Could not find user {username}.");
+        std::process::exit(1);
+    };
+    // Jellyfin usernames are case insensitive, so only a change of case may reuse the current name.
+    if users.iter().any(|u| u.id != user.id && u.name.eq_ignore_ascii_case(new_name)) {
+        eprintln!("A user named {new_name} already exists.");
+        std::process::exit(1);
+    }
+    let old_name = std::mem::replace(&mut user.name, new_name.to_owned());
+    exit_on_error(
+        UserList::update_user(UserList::new(user_id_endpoint, &cfg.server_url, &cfg.api_key), &user, &user.id)
+            .map_err(|e| format!("Unable to rename {old_name}.  {e}")),
+    );
+    println!("User {old_name} renamed to {new_name}.");
+    // Role assignments are remembered by name.
+    if let Some(role) = cfg.role_assignments.remove(&old_name) {
+        cfg.role_assignments.insert(new_name.to_owned(), role);
+        store_config(&cfg);
+    }
+}
+
+pub fn command_user_prefs_get(cfg: &AppConfig, username: &str, key: Option<&str>, users_endpoint: &str) {
+    let user = exit_on_error(find_user(cfg, users_endpoint, username));
+    let values = configuration_to_map(&user.configuration);
+    match key {
+        None => println!("{}", serde_json::to_string_pretty(&values).unwrap()),
+        Some(key) => match exit_on_error(setting_entry(&values, key, "preference")) {
+            (_, Value::String(s)) => println!("{s}"),
+            (_, v) => println!("{v}"),
+        },
+    }
+}
+
+///
+/// Changes preferences of the users chosen by the selector.  Each setting comes from a --set Key=Value option and
+/// is applied to every selected user.
+///
+pub fn command_user_prefs_set(cfg: &AppConfig, selector: &UserSelector, settings: &[String], users_endpoint: &str, user_configuration_endpoint: &str) {
+    if let Some(setting) = settings.iter().find(|s| !s.contains('=')) {
+        eprintln!("Invalid preference \"{setting}\".  Preferences are set as Key=Value, e.g. --set SubtitleMode=Smart.");
+        std::process::exit(1);
+    }
+    let users = exit_on_error(select_users(cfg, selector, users_endpoint));
+    run_for_users(&users, "successfully updated", |user| {
+        let mut values = configuration_to_map(&user.configuration);
+        for setting in settings {
+            let (key, raw) = setting.split_once('=').unwrap_or_default();
+            let (name, current) = setting_entry(&values, key.trim(), "preference")?;
+            let name = name.to_owned();
+            let new_value = parse_preference_value(&name, current, raw)?;
+            if *current != new_value {
+                println!("  {}: {name}: {current} -> {new_value}", user.name);
+            }
+            values.insert(name, new_value);
+        }
+        let configuration: Configuration =
+            serde_json::from_value(Value::Object(values)).map_err(|e| format!("Unable to apply preference change, {e}"))?;
+        UserList::update_user_configuration(UserList::new(user_configuration_endpoint, &cfg.server_url, &cfg.api_key), &configuration, &user.id)
+            .map_err(|e| e.to_string())
+    });
 }
 
 pub fn command_update_users(cfg: &AppConfig, inputfile: String, passed_user_id: &str) {
@@ -201,24 +858,84 @@ pub fn command_update_profile_picture(cfg: &AppConfig, username: &str, path: &st
 
 }
 
-pub fn command_remove_device_by_username(cfg: &AppConfig, username: &str, devices_endpoint: &str) {
-    let filtered: Vec<String> = match get_deviceid_by_username(
-        ServerInfo::new(devices_endpoint, &cfg.server_url, &cfg.api_key),
-        username,
-    ) {
-        Err(_) => {
-            eprintln!("Unable to get device id by username.");
-            std::process::exit(1);
+pub fn command_remove_device_by_username(cfg: &AppConfig, selector: &UserSelector, yes: bool, users_endpoint: &str, devices_endpoint: &str) {
+    let users = select_confirmed_users(cfg, selector, yes, "remove the devices of", users_endpoint);
+    run_for_users(&users, "had their devices removed", |user| {
+        let devices = get_deviceid_by_username(ServerInfo::new(devices_endpoint, &cfg.server_url, &cfg.api_key), &user.name)
+            .map_err(|e| format!("Unable to get device id by username, {e}"))?;
+        for device in devices {
+            remove_device(ServerInfo::new(devices_endpoint, &cfg.server_url, &cfg.api_key), &device)
+                .map_err(|e| format!("Unable to delete device {device}, {e}"))?;
+        }
+        Ok(())
+    });
+}
+
+///
+/// Resolves a user selector against the server's users.  Named users that do not exist are an error so
+/// that a typo never silently skips an account.
+///
+pub fn select_users(cfg: &AppConfig, selector: &UserSelector, users_endpoint: &str) -> Result<Vec<UserDetails>, String> {
+    let pattern = selector
+        .pattern
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| format!("Invalid --match pattern, {e}"))?;
+    let inactive_since = selector
+        .inactive_since
+        .as_deref()
+        .map(|date| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map(|d| d.and_time(NaiveTime::MIN).and_utc())
+                .map_err(|e| format!("Invalid --inactive-since date \"{date}\", expected YYYY-MM-DD.  {e}"))
+        })
+        .transpose()?;
+
+    let mut names = selector.users.clone();
+    if let Some(path) = &selector.from_file {
+        let data = fs::read_to_string(path).map_err(|e| format!("Unable to read {path}, {e}"))?;
+        let before = names.len();
+        names.extend(
+            data.lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(str::to_owned),
+        );
+        // An empty file must not fall back to selecting every user.
+        if names.len() == before {
+            return Err(format!("{path} does not contain any usernames."));
         }
-        Ok(i) => i,
-    };
-    for item in filtered {
-        remove_device(
-            ServerInfo::new(devices_endpoint, &cfg.server_url, &cfg.api_key),
-            &item,
-        )
-        .expect("Unable to delete specified id.");
     }
+    let every_user = selector.users.is_empty() && selector.from_file.is_none();
+
+    let users = UserList::list_users(UserList::new(users_endpoint, &cfg.server_url, &cfg.api_key))
+        .map_err(|e| format!("Unable to gather users.  {e}"))?;
+    let missing: Vec<&String> = names.iter().filter(|n| !users.iter().any(|u| u.name.eq_ignore_ascii_case(n))).collect();
+    if !missing.is_empty() {
+        return Err(format!("Unknown user(s): {}", missing.iter().map(|n| n.as_str()).collect::<Vec<&str>>().join(", ")));
+    }
+
+    let selected: Vec<UserDetails> = users
+        .into_iter()
+        .filter(|u| every_user || names.iter().any(|n| u.name.eq_ignore_ascii_case(n)))
+        .filter(|u| pattern.as_ref().is_none_or(|r| r.is_match(&u.name)))
+        .filter(|u| !selector.admins || u.policy.is_administrator)
+        .filter(|u| !selector.disabled || u.policy.is_disabled)
+        .filter(|u| {
+            inactive_since.is_none_or(|since| {
+                [parse_jellyfin_date(u.last_activity_date.as_deref()), parse_jellyfin_date(u.last_login_date.as_deref())]
+                    .into_iter()
+                    .flatten()
+                    .max()
+                    .is_none_or(|last| last < since)
+            })
+        })
+        .collect();
+    if selected.is_empty() {
+        return Err("No users match the selection.".to_owned());
+    }
+    Ok(selected)
 }
 
 /* 
@@ -255,36 +972,673 @@ fn gather_user_information(cfg: &AppConfig, username: &str, id: &str, user_id: &
 /// Helper function to standardize the call for adding a user with a password.
 ///
 fn add_user(cfg: &AppConfig, username: String, password: String) {
+    match try_add_user(cfg, username, password) {
+        Err(e) => {
+            println!("Unable to create user.  {e}");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    }
+}
+
+///
+/// Creates a user, returning any failure to the caller rather than exiting.
+///
+fn try_add_user(cfg: &AppConfig, username: String, password: String) -> Result<(), Box<dyn std::error::Error>> {
     let server_path = format!("{}/Users/New", cfg.server_url);
-    match UserWithPass::create_user(UserWithPass::new(
+    UserWithPass::create_user(UserWithPass::new(
         Some(username),
         Some(password),
         None,
         server_path,
         cfg.api_key.clone(),
-    )) {
-        Err(_) => {
-            println!("Unable to create user");
+    ))
+}
+
+///
+/// Creates a single imported user and applies any policy values from the import row.  Generated credentials are
+/// recorded as soon as the user exists so they are not lost if applying the policy fails.
+///
+fn import_user(import: &mut UserImportContext, user: UserImport) -> Result<(), String> {
+    let cfg = import.cfg;
+    if user.username.is_empty() {
+        return Err("Missing username.".to_owned());
+    }
+
+    // Resolve library names before creating the user so a typo doesn't leave a half configured account behind.
+    let library_ids = match &user.libraries {
+        None => None,
+        Some(names) => {
+            if import.libraries.is_none() {
+                import.libraries = Some(fetch_libraries(cfg)?);
+            }
+            let all = import.libraries.as_deref().unwrap_or_default();
+            Some(resolve_library_ids(all, names, &user.username)?)
+        }
+    };
+
+    let password = match (import.generation, &user.password) {
+        (Some(options), _) => generate_password(options),
+        (None, Some(p)) => p.clone(),
+        (None, None) => prompt_password(&format!("Please enter the password for {}: ", user.username), true),
+    };
+    try_add_user(cfg, user.username.clone(), password.clone())
+        .map_err(|e| format!("Unable to create user \"{}\".  {e}", user.username))?;
+    if import.generation.is_some() {
+        import.credentials.push(Credential { username: user.username.clone(), password });
+    }
+
+    if !user.has_policy() {
+        return Ok(());
+    }
+    let policy_error = |e: String| format!("User \"{}\" was created but the policy could not be applied.  {e}", user.username);
+    let created = find_user(cfg, import.users_endpoint, &user.username).map_err(policy_error)?;
+    let id = created.id;
+    let mut policy = created.policy;
+    if let Some(admin) = user.admin {
+        policy.is_administrator = admin;
+    }
+    if let Some(disabled) = user.disabled {
+        policy.is_disabled = disabled;
+    }
+    if let Some(hidden) = user.hidden {
+        policy.is_hidden = hidden;
+    }
+    if let Some(ids) = library_ids {
+        policy.enable_all_folders = false;
+        policy.enabled_folders = ids;
+    }
+    if let Some(rating) = user.max_parental_rating {
+        policy.max_parental_rating = Some(rating);
+    }
+    if let Some(remote) = user.remote_access {
+        policy.enable_remote_access = remote;
+    }
+    UserList::update_user_policy(UserList::new(import.user_policy_endpoint, &cfg.server_url, &cfg.api_key), &policy, &id)
+        .map_err(|e| policy_error(e.to_string()))?;
+    println!("Policy applied to user \"{}\".", user.username);
+    Ok(())
+}
+
+///
+/// Retrieve the details, including the policy, of the specified user.
+///
+pub fn find_user(cfg: &AppConfig, users_endpoint: &str, username: &str) -> Result<UserDetails, String> {
+    let users = UserList::list_users(UserList::new(users_endpoint, &cfg.server_url, &cfg.api_key))
+        .map_err(|e| format!("Unable to gather users.  {e}"))?;
+    users
+        .into_iter()
+        .find(|u| u.name.eq_ignore_ascii_case(username))
+        .ok_or_else(|| format!("Could not find user {username}."))
+}
+
+///
+/// Converts library names into the ids used by the user policy.
+///
+fn resolve_library_ids(libraries: &[LibraryDetails], names: &[String], username: &str) -> Result<Vec<String>, String> {
+    names
+        .iter()
+        .map(|name| {
+            libraries
+                .iter()
+                .find(|l| l.name.eq_ignore_ascii_case(name))
+                .map(|l| l.item_id.clone())
+                .ok_or_else(|| format!("Unknown library \"{name}\" for user \"{username}\"."))
+        })
+        .collect()
+}
+
+fn read_manifest(manifest_file: &str) -> UserManifest {
+    match UserManifest::read_file(manifest_file) {
+        Err(e) => {
+            eprintln!("{e}");
             std::process::exit(1);
         }
         Ok(i) => i,
     }
 }
 
+///
+/// Compares the manifest against the users on the server.  Only the settings a manifest user specifies are
+/// managed; everything else is left as the server has it.
+///
+fn plan_users(cfg: &AppConfig, manifest: &UserManifest, unmanaged_users: UnmanagedUsers, users_endpoint: &str) -> Result<UserPlan, String> {
+    let existing = UserList::list_users(UserList::new(users_endpoint, &cfg.server_url, &cfg.api_key))
+        .map_err(|e| format!("Unable to gather users.  {e}"))?;
+    let libraries = if manifest.users.iter().any(|u| u.libraries.is_some()) {
+        fetch_libraries(cfg)?
+    } else {
+        Vec::new()
+    };
+
+    let mut changes = Vec::new();
+    for user in &manifest.users {
+        match existing.iter().find(|u| u.name.eq_ignore_ascii_case(&user.name)) {
+            None => {
+                // Catch unknown libraries now rather than after the user has been created.
+                apply_manifest_policy(&mut Policy::default(), user, &libraries, "")?;
+                changes.push(PlannedChange::Create(Box::new(user.clone())));
+            }
+            Some(current) => {
+                let mut policy = current.policy.clone();
+                let differences = apply_manifest_policy(&mut policy, user, &libraries, &current.id)?;
+                if !differences.is_empty() {
+                    changes.push(PlannedChange::Update {
+                        name: current.name.clone(),
+                        id: current.id.clone(),
+                        policy: Box::new(policy),
+                        differences,
+                    });
+                }
+            }
+        }
+    }
+
+    let unmanaged: Vec<&UserDetails> = existing
+        .iter()
+        .filter(|u| !manifest.users.iter().any(|m| m.name.eq_ignore_ascii_case(&u.name)))
+        .collect();
+    let (mut ignored, mut kept_admins) = (0, 0);
+    for user in unmanaged {
+        match unmanaged_users {
+            UnmanagedUsers::Keep => ignored += 1,
+            UnmanagedUsers::Delete if user.policy.is_administrator => kept_admins += 1,
+            UnmanagedUsers::Delete | UnmanagedUsers::DeleteIncludingAdmins => {
+                changes.push(PlannedChange::Delete { name: user.name.clone(), id: user.id.clone() });
+            }
+        }
+    }
+
+    Ok(UserPlan { changes, ignored, kept_admins, libraries })
+}
+
+fn print_plan(plan: &UserPlan) {
+    let (mut create, mut update, mut delete) = (0, 0, 0);
+    if plan.changes.is_empty() {
+        println!("No changes.  Users on the server match the manifest.");
+    } else {
+        println!("Users will be changed as follows:\n");
+    }
+    for change in &plan.changes {
+        match change {
+            PlannedChange::Create(user) => {
+                create += 1;
+                println!("  + create \"{}\"", user.name);
+                for line in describe_manifest_user(user) {
+                    println!("      {line}");
+                }
+            }
+            PlannedChange::Update { name, differences, .. } => {
+                update += 1;
+                println!("  ~ update \"{name}\"");
+                for line in differences {
+                    println!("      {line}");
+                }
+            }
+            PlannedChange::Delete { name, .. } => {
+                delete += 1;
+                println!("  - delete \"{name}\"");
+            }
+        }
+    }
+    if !plan.changes.is_empty() {
+        println!("\nPlan: {create} to create, {update} to update, {delete} to delete.");
+    }
+    if plan.ignored > 0 {
+        println!("{} user(s) not in the manifest will be left alone.  Use --delete-unmanaged to remove them.", plan.ignored);
+    }
+    if plan.kept_admins > 0 {
+        println!("{} administrator(s) not in the manifest will be kept.  Use --include-admins to delete them as well.", plan.kept_admins);
+    }
+}
+
+///
+/// Creates a user from the manifest and applies its settings.
+///
+fn create_manifest_user(
+    cfg: &AppConfig,
+    user: &ManifestUser,
+    password: String,
+    libraries: &[LibraryDetails],
+    users_endpoint: &str,
+    user_policy_endpoint: &str,
+) -> Result<(), String> {
+    try_add_user(cfg, user.name.clone(), password).map_err(|e| format!("Unable to create user \"{}\".  {e}", user.name))?;
+    let policy_error = |e: String| format!("User \"{}\" was created but the policy could not be applied.  {e}", user.name);
+    let created = find_user(cfg, users_endpoint, &user.name).map_err(policy_error)?;
+    let mut policy = created.policy;
+    if apply_manifest_policy(&mut policy, user, libraries, &created.id)?.is_empty() {
+        return Ok(());
+    }
+    UserList::update_user_policy(UserList::new(user_policy_endpoint, &cfg.server_url, &cfg.api_key), &policy, &created.id)
+        .map_err(|e| policy_error(e.to_string()))
+}
+
+///
+/// Updates the policy with the settings from the manifest and describes each value that changed.
+///
+fn apply_manifest_policy(policy: &mut Policy, user: &ManifestUser, libraries: &[LibraryDetails], user_id: &str) -> Result<Vec<String>, String> {
+    let mut differences = Vec::new();
+    let mut set_bool = |label: &str, current: &mut bool, desired: Option<bool>| {
+        if let Some(desired) = desired
+            && *current != desired
+        {
+            differences.push(format!("{label}: {current} -> {desired}"));
+            *current = desired;
+        }
+    };
+    set_bool("admin", &mut policy.is_administrator, user.admin);
+    set_bool("disabled", &mut policy.is_disabled, user.disabled);
+    set_bool("hidden", &mut policy.is_hidden, user.hidden);
+    set_bool("remote_access", &mut policy.enable_remote_access, user.remote_access);
+
+    if let Some(rating) = user.max_parental_rating
+        && policy.max_parental_rating != Some(rating)
+    {
+        let current = policy.max_parental_rating.map_or_else(|| "none".to_owned(), |r| r.to_string());
+        differences.push(format!("max_parental_rating: {current} -> {rating}"));
+        policy.max_parental_rating = Some(rating);
+    }
+
+    if let Some(names) = &user.libraries {
+        let current = describe_libraries(policy, libraries);
+        if names.iter().any(|n| n == "*") {
+            if !policy.enable_all_folders {
+                policy.enable_all_folders = true;
+                policy.enabled_folders = Vec::new();
+            }
+        } else {
+            let mut ids = resolve_library_ids(libraries, names, &user.name)?;
+            ids.sort();
+            let mut current_ids = policy.enabled_folders.clone();
+            current_ids.sort();
+            if policy.enable_all_folders || current_ids != ids {
+                policy.enable_all_folders = false;
+                policy.enabled_folders = ids;
+            }
+        }
+        let desired = describe_libraries(policy, libraries);
+        if current != desired {
+            differences.push(format!("libraries: {current} -> {desired}"));
+        }
+    }
+
+    if let Some(schedules) = &user.schedules {
+        let desired: Vec<AccessSchedule> = schedules.iter().map(|s| s.to_access_schedule(user_id)).collect();
+        let current = describe_schedules(&policy.access_schedules);
+        let wanted = describe_schedules(&desired);
+        if current != wanted {
+            differences.push(format!("schedules: {current} -> {wanted}"));
+            policy.access_schedules = desired;
+        }
+    }
+
+    Ok(differences)
+}
+
+///
+/// Lists the settings a new user will be created with.
+///
+fn describe_manifest_user(user: &ManifestUser) -> Vec<String> {
+    let mut lines = Vec::new();
+    if user.password.is_none() {
+        lines.push("password: (generated)".to_owned());
+    }
+    for (label, value) in [
+        ("admin", user.admin),
+        ("disabled", user.disabled),
+        ("hidden", user.hidden),
+        ("remote_access", user.remote_access),
+    ] {
+        if let Some(value) = value {
+            lines.push(format!("{label}: {value}"));
+        }
+    }
+    if let Some(rating) = user.max_parental_rating {
+        lines.push(format!("max_parental_rating: {rating}"));
+    }
+    if let Some(names) = &user.libraries {
+        lines.push(format!("libraries: {}", names.join(", ")));
+    }
+    if let Some(schedules) = &user.schedules {
+        let schedules: Vec<String> = schedules.iter().map(|s| s.to_access_schedule("").to_string()).collect();
+        lines.push(format!("schedules: {}", schedules.join(", ")));
+    }
+    lines
+}
+
+fn describe_libraries(policy: &Policy, libraries: &[LibraryDetails]) -> String {
+    if policy.enable_all_folders {
+        return "all".to_owned();
+    }
+    if policy.enabled_folders.is_empty() {
+        return "none".to_owned();
+    }
+    library_names(policy, libraries).join(", ")
+}
+
+///
+/// Names of the libraries the policy explicitly allows.  Ids that no longer match a library are shown as is.
+///
+fn library_names(policy: &Policy, libraries: &[LibraryDetails]) -> Vec<String> {
+    let mut names: Vec<String> = policy
+        .enabled_folders
+        .iter()
+        .map(|id| libraries.iter().find(|l| &l.item_id == id).map_or_else(|| id.clone(), |l| l.name.clone()))
+        .collect();
+    names.sort();
+    names
+}
+
+fn describe_schedules(schedules: &[AccessSchedule]) -> String {
+    if schedules.is_empty() {
+        return "none".to_owned();
+    }
+    schedules.iter().map(ToString::to_string).collect::<Vec<String>>().join(", ")
+}
+
+fn schedule_from_window(window: &ScheduleWindow, user_id: &str) -> AccessSchedule {
+    AccessSchedule {
+        user_id: user_id.to_owned(),
+        day_of_week: window.day.to_string(),
+        start_hour: window.from,
+        end_hour: window.to,
+    }
+}
+
+///
+/// Applies a change to the access schedules of each targeted user.  A failure for one user is reported and the
+/// remaining users are still updated.
+///
+fn change_schedules<F>(cfg: &AppConfig, targets: &ScheduleTargets, users_endpoint: &str, user_policy_endpoint: &str, change: F)
+where
+    F: Fn(&mut Vec<AccessSchedule>, &str) -> Result<(), String>,
+{
+    let usernames: Vec<String> = match &targets.role {
+        None => targets.users.clone(),
+        Some(role) => {
+            let assigned: Vec<String> = cfg
+                .role_assignments
+                .iter()
+                .filter(|(_, r)| *r == role)
+                .map(|(user, _)| user.clone())
+                .collect();
+            if assigned.is_empty() {
+                eprintln!("No users are assigned to role \"{role}\".");
+                std::process::exit(1);
+            }
+            assigned
+        }
+    };
+
+    let mut failed = false;
+    for username in usernames {
+        let result = find_user(cfg, users_endpoint, &username).and_then(|user| {
+            let mut policy = user.policy;
+            let before = describe_schedules(&policy.access_schedules);
+            change(&mut policy.access_schedules, &user.id)?;
+            let after = describe_schedules(&policy.access_schedules);
+            if before == after {
+                println!("No changes for {}.", user.name);
+                return Ok(());
+            }
+            UserList::update_user_policy(UserList::new(user_policy_endpoint, &cfg.server_url, &cfg.api_key), &policy, &user.id)
+                .map_err(|e| e.to_string())?;
+            println!("Access schedules for {} updated.", user.name);
+            println!("  schedules: {before} -> {after}");
+            Ok(())
+        });
+        if let Err(e) = result {
+            eprintln!("[ERROR] {username}: {e}");
+            failed = true;
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn fetch_libraries(cfg: &AppConfig) -> Result<Vec<LibraryDetails>, String> {
+    get_libraries(ServerInfo::new("/Library/VirtualFolders", &cfg.server_url, &cfg.api_key))
+        .map_err(|e| format!("Unable to get libraries, {e}"))
+}
+
+///
+/// Applies a change to the library access of a user and reports the access before and after.
+///
+fn change_library_access<F>(cfg: &AppConfig, username: &str, users_endpoint: &str, user_policy_endpoint: &str, change: F)
+where
+    F: FnOnce(&mut Policy, &[LibraryDetails], &str) -> Result<(), String>,
+{
+    let user = exit_on_error(find_user(cfg, users_endpoint, username));
+    let libraries = exit_on_error(fetch_libraries(cfg));
+    let mut policy = user.policy;
+    let before = describe_libraries(&policy, &libraries);
+    exit_on_error(change(&mut policy, &libraries, &user.name));
+    let after = describe_libraries(&policy, &libraries);
+    if before == after {
+        println!("No changes.  {} has access to: {before}", user.name);
+        return;
+    }
+    exit_on_error(
+        UserList::update_user_policy(UserList::new(user_policy_endpoint, &cfg.server_url, &cfg.api_key), &policy, &user.id)
+            .map_err(|e| e.to_string()),
+    );
+    println!("Library access for {} updated.", user.name);
+    println!("  libraries: {before} -> {after}");
+}
+
+///
+/// Converts the policy into a key/value map keyed by the names Jellyfin uses, e.g. "EnableRemoteAccess".
+///
+pub fn policy_to_map(policy: &Policy) -> Map<String, Value> {
+    match serde_json::to_value(policy) {
+        Ok(Value::Object(map)) => map,
+        _ => {
+            eprintln!("Unable to read user policy.");
+            std::process::exit(1);
+        }
+    }
+}
+
+///
+/// Finds a policy value by name, ignoring case.
+///
+pub fn policy_entry<'a>(values: &'a Map<String, Value>, key: &str) -> Result<(&'a str, &'a Value), String> {
+    setting_entry(values, key, "policy")
+}
+
+fn setting_entry<'a>(values: &'a Map<String, Value>, key: &str, kind: &str) -> Result<(&'a str, &'a Value), String> {
+    values
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(key))
+        .map(|(name, value)| (name.as_str(), value))
+        .ok_or_else(|| {
+            format!(
+                "Unknown {kind} key \"{key}\".  Valid keys are: {}",
+                values.keys().map(String::as_str).collect::<Vec<&str>>().join(", ")
+            )
+        })
+}
+
+///
+/// Parses a value for a policy key.  Schedules and the optional parental rating need special handling, everything
+/// else is parsed by type.
+///
+fn parse_policy_value(key: &str, current: &Value, raw: &str) -> Result<Value, String> {
+    // Schedules are objects rather than plain values.
+    if key == "AccessSchedules" {
+        return Err(format!("Policy key \"{key}\" cannot be set with user-policy."));
+    }
+    // The parental rating limit is optional, where "none" removes the limit.
+    if current.is_null() || key == "MaxParentalRating" {
+        let raw = raw.trim();
+        if raw.is_empty() || raw.eq_ignore_ascii_case("none") {
+            return Ok(Value::Null);
+        }
+        return raw
+            .parse::<i64>()
+            .map(Value::from)
+            .map_err(|_| format!("Policy key \"{key}\" expects a whole number or none."));
+    }
+    parse_setting_value("Policy", key, current, raw)
+}
+
+///
+/// Parses a value using the type of the current value to decide how.  Lists are given as comma separated values.
+///
+fn parse_setting_value(kind: &str, key: &str, current: &Value, raw: &str) -> Result<Value, String> {
+    match current {
+        Value::Bool(_) => raw
+            .trim()
+            .to_lowercase()
+            .parse::<bool>()
+            .map(Value::Bool)
+            .map_err(|_| format!("{kind} key \"{key}\" expects true or false.")),
+        Value::Number(_) => raw
+            .trim()
+            .parse::<i64>()
+            .map(Value::from)
+            .map_err(|_| format!("{kind} key \"{key}\" expects a whole number.")),
+        Value::String(_) => Ok(Value::String(raw.to_owned())),
+        Value::Array(_) => Ok(Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| Value::String(s.to_owned()))
+                .collect(),
+        )),
+        _ => Err(format!("{kind} key \"{key}\" cannot be set from the command line.")),
+    }
+}
+
+///
+/// Parses a preference value.  The subtitle mode only accepts the modes Jellyfin knows about.
+///
+fn parse_preference_value(key: &str, current: &Value, raw: &str) -> Result<Value, String> {
+    if key == "SubtitleMode" {
+        return SUBTITLE_MODES
+            .iter()
+            .find(|m| m.eq_ignore_ascii_case(raw.trim()))
+            .map(|m| Value::String((*m).to_owned()))
+            .ok_or_else(|| format!("Preference key \"{key}\" must be one of {}.", SUBTITLE_MODES.join(", ")));
+    }
+    // Language preferences are unset on new users.
+    if current.is_null() {
+        return Ok(Value::String(raw.to_owned()));
+    }
+    parse_setting_value("Preference", key, current, raw)
+}
+
+fn configuration_to_map(configuration: &Configuration) -> Map<String, Value> {
+    match serde_json::to_value(configuration) {
+        Ok(Value::Object(map)) => map,
+        _ => {
+            eprintln!("Unable to read user preferences.");
+            std::process::exit(1);
+        }
+    }
+}
+
 ///
 /// Function to modify user information
 /// 
-fn modify_user(cfg: &AppConfig, username: &str, user_policy_endpoint: &str, user_id_endpoint: &str, mod_type: &UserMods, mod_flag: bool) {
-    let id = get_user_id(cfg, username);
-    let mut user_info = gather_user_information(cfg, username, &id, user_id_endpoint);
-    match mod_type {
-        UserMods::Admin => user_info.policy.is_administrator = mod_flag,
-        UserMods::Active => user_info.policy.is_disabled = mod_flag
-    }
-    UserList::update_user_config_bool(
-        UserList::new(user_policy_endpoint, &cfg.server_url, &cfg.api_key),
-        &user_info.policy,
-        &id,
-        username,
-    ).expect("Unable to update user.");
-}
\ No newline at end of file
+fn modify_users(cfg: &AppConfig, users: &[UserDetails], user_policy_endpoint: &str, mod_type: &UserMods, mod_flag: bool) {
+    run_for_users(users, "successfully updated", |user| {
+        let mut policy = user.policy.clone();
+        match mod_type {
+            UserMods::Admin => policy.is_administrator = mod_flag,
+            UserMods::Active => policy.is_disabled = mod_flag
+        }
+        UserList::update_user_policy(UserList::new(user_policy_endpoint, &cfg.server_url, &cfg.api_key), &policy, &user.id)
+            .map_err(|e| e.to_string())
+    });
+}
+
+///
+/// Resolves a user selector and, when users were chosen by a selector rather than by name, lists them and asks
+/// before anything is changed.
+///
+pub fn select_confirmed_users(cfg: &AppConfig, selector: &UserSelector, yes: bool, action: &str, users_endpoint: &str) -> Vec<UserDetails> {
+    let users = exit_on_error(select_users(cfg, selector, users_endpoint));
+    if yes || !selector.is_bulk() {
+        return users;
+    }
+    println!("The following users are selected:\n");
+    for user in &users {
+        println!("  {}", user.name);
+    }
+    if !confirm(&format!("Do you want to {action} {} user(s)?", users.len())) {
+        println!("Cancelled, no users were changed.");
+        std::process::exit(1);
+    }
+    users
+}
+
+///
+/// Refuses a change that would leave the server without an enabled administrator, as nobody could manage it
+/// afterwards.  The users given are the ones about to be deleted, disabled or demoted.
+///
+fn keep_an_administrator(cfg: &AppConfig, removed: &[UserDetails], users_endpoint: &str) -> Result<(), String> {
+    let is_active_admin = |user: &UserDetails| user.policy.is_administrator && !user.policy.is_disabled;
+    if !removed.iter().any(is_active_admin) {
+        return Ok(());
+    }
+    let users = UserList::list_users(UserList::new(users_endpoint, &cfg.server_url, &cfg.api_key))
+        .map_err(|e| format!("Unable to gather users.  {e}"))?;
+    if users.iter().any(|u| is_active_admin(u) && !removed.iter().any(|r| r.id == u.id)) {
+        return Ok(());
+    }
+    Err("Refusing to remove the last enabled administrator.  Grant another user admin rights first.".to_owned())
+}
+
+///
+/// Runs an action for each selected user, carrying on after failures and summarising the results.
+///
+fn run_for_users<F>(users: &[UserDetails], done: &str, action: F)
+where
+    F: Fn(&UserDetails) -> Result<(), String>,
+{
+    let mut failed = 0;
+    for user in users {
+        match action(user) {
+            Ok(()) => println!("User {} {done}.", user.name),
+            Err(e) => {
+                eprintln!("[ERROR] {}: {e}", user.name);
+                failed += 1;
+            }
+        }
+    }
+    if users.len() > 1 || failed > 0 {
+        println!("{} succeeded, {failed} failed.", users.len() - failed);
+    }
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+///
+/// Asks a yes/no question, only accepting "yes" as approval.
+///
+fn confirm(question: &str) -> bool {
+    print!("\n[INPUT] {question}  Only 'yes' will be accepted: ");
+    io::stdout().flush().expect("Unable to get user information.");
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).expect("Could not read user input.");
+    answer.trim() == "yes"
+}
+
+///
+/// The prune journal is kept next to the configuration file so each profile has its own.
+///
+fn journal_path(cfg: &AppConfig) -> PathBuf {
+    cfg.config_path.with_extension("journal")
+}
+
+///
+/// Converts an --exclude pattern such as "svc-*" into a case insensitive regular expression.
+///
+fn glob_to_regex(pattern: &str) -> Result<Regex, String> {
+    Regex::new(&format!("(?i)^{}$", regex::escape(pattern).replace(r"\*", ".*")))
+        .map_err(|e| format!("Invalid pattern \"{pattern}\", {e}"))
+}
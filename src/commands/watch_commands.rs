@@ -0,0 +1,395 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{SecondsFormat, Utc};
+
+use crate::{AppConfig, HistoryFormat, ItemSelector,
+    commands::{media_commands::execute_search, user_commands::find_user},
+    entities::{external_history::{read_letterboxd, read_trakt}, media_details::MediaItem, server_info::ServerInfo, user_details::UserDetails, watch_state::{UserItemData, WatchStateExport, WatchStateItem}},
+    system_actions::{get_libraries, get_search_results, set_item_played, update_user_item_data},
+    utils::common::{exit_on_error, parse_jellyfin_date}};
+
+const USER_ITEM_DATA: &str = "/UserItems/{itemId}/UserData";
+const USER_PLAYED_ITEMS: &str = "/UserPlayedItems/{itemId}";
+const WATCHED_TYPES: &str = "Movie,Series,Episode";
+const PLAYABLE_TYPES: &str = "Movie,Episode,Video";
+
+pub fn command_watch_state_export(cfg: &AppConfig, username: &str, output: Option<&str>, users_endpoint: &str) {
+    let user = exit_on_error(find_user(cfg, users_endpoint, username));
+    let mut items: HashMap<String, WatchStateItem> = HashMap::new();
+    for filter in ["IsPlayed", "IsResumable", "IsFavorite"] {
+        for item in exit_on_error(user_items(cfg, &user.id, Some(filter))) {
+            items.entry(item.id.clone()).or_insert_with(|| WatchStateItem::from_media_item(&item));
+        }
+    }
+    let mut items: Vec<WatchStateItem> = items.into_values().collect();
+    items.sort_by_key(|i| (i.series_name.clone().unwrap_or_else(|| i.name.clone()), i.season, i.episode, i.name.clone()));
+
+    let export = WatchStateExport {
+        user: user.name,
+        server: cfg.server_url.clone(),
+        exported: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        items,
+    };
+    exit_on_error(export.write(output));
+    if let Some(path) = output {
+        println!("Exported the watch state of {} item(s) for {} to {path}.", export.items.len(), export.user);
+    }
+}
+
+pub fn command_watch_state_import(cfg: &AppConfig, username: &str, file: &str, dry_run: bool, users_endpoint: &str) {
+    let export = exit_on_error(WatchStateExport::read_file(file));
+    let user = exit_on_error(find_user(cfg, users_endpoint, username));
+    println!("Importing the watch state of {} item(s) exported for {} from {}.", export.items.len(), export.user, export.server);
+    apply_watch_state(cfg, &user, &export.items, dry_run);
+}
+
+pub fn command_watch_state_import_external(cfg: &AppConfig, username: &str, file: &str, format: &HistoryFormat, dry_run: bool, users_endpoint: &str) {
+    let rows = exit_on_error(match format {
+        HistoryFormat::Trakt => read_trakt(file),
+        HistoryFormat::Letterboxd => read_letterboxd(file),
+    });
+    let user = exit_on_error(find_user(cfg, users_endpoint, username));
+
+    // Exports list every play separately, so rewatches are folded into a single entry per item.
+    let mut entries: BTreeMap<String, WatchStateItem> = BTreeMap::new();
+    let mut invalid = 0;
+    for row in rows {
+        match row {
+            Err(e) => {
+                eprintln!("[ERROR] Skipping {e}");
+                invalid += 1;
+            }
+            Ok(entry) => match entries.get_mut(&history_key(&entry)) {
+                None => {
+                    entries.insert(history_key(&entry), entry);
+                }
+                Some(existing) => {
+                    existing.play_count += entry.play_count;
+                    existing.provider_ids.extend(entry.provider_ids);
+                    if entry.last_played_date > existing.last_played_date {
+                        existing.last_played_date = entry.last_played_date;
+                    }
+                }
+            },
+        }
+    }
+    println!("Importing {} watched item(s) from {file} for {}.", entries.len(), user.name);
+    let entries: Vec<WatchStateItem> = entries.into_values().collect();
+    apply_watch_state(cfg, &user, &entries, dry_run);
+    if invalid > 0 {
+        eprintln!("{invalid} row(s) of {file} could not be read.");
+        std::process::exit(1);
+    }
+}
+
+pub fn command_played_set(cfg: &AppConfig, username: &str, selector: &ItemSelector, played: bool, dry_run: bool, users_endpoint: &str) {
+    let user = exit_on_error(find_user(cfg, users_endpoint, username));
+    let items = exit_on_error(select_items(cfg, selector));
+    let server_info = ServerInfo::new(USER_PLAYED_ITEMS, &cfg.server_url, &cfg.api_key);
+    let done = if played { "marked played" } else { "marked unplayed" };
+    run_for_items(&items, done, dry_run, |item| {
+        set_item_played(&server_info, &item.id, &user.id, played).map_err(|e| e.to_string())
+    });
+}
+
+pub fn command_progress_reset(cfg: &AppConfig, username: &str, selector: &ItemSelector, dry_run: bool, users_endpoint: &str) {
+    let user = exit_on_error(find_user(cfg, users_endpoint, username));
+    let items = exit_on_error(select_items(cfg, selector));
+    let server_info = ServerInfo::new(USER_ITEM_DATA, &cfg.server_url, &cfg.api_key);
+    let data = UserItemData { playback_position_ticks: Some(0), ..UserItemData::default() };
+    run_for_items(&items, "reset", dry_run, |item| {
+        update_user_item_data(&server_info, &item.id, &user.id, &data).map_err(|e| e.to_string())
+    });
+}
+
+/*
+    The following section contains additional
+    functions that are used to support the watch_commands
+    base functions.
+*/
+
+// Items of the server indexed by the ways a watch state entry can be matched to them.
+struct ItemIndex<'a> {
+    by_provider: HashMap<String, Vec<&'a MediaItem>>,
+    by_episode: HashMap<(String, i64, i64), Vec<&'a MediaItem>>,
+    by_title: HashMap<(String, String, i64), Vec<&'a MediaItem>>,
+}
+
+enum ItemMatch<'a> {
+    Found(&'a MediaItem),
+    Ambiguous(usize),
+    Missing,
+}
+
+impl<'a> ItemIndex<'a> {
+    fn new(items: &'a [MediaItem]) -> ItemIndex<'a> {
+        let mut index = ItemIndex { by_provider: HashMap::new(), by_episode: HashMap::new(), by_title: HashMap::new() };
+        for item in items {
+            for (provider, id) in &item.provider_ids.ids {
+                index.by_provider.entry(provider_key(&item.type_field, provider, id)).or_default().push(item);
+            }
+            if item.type_field == "Episode" {
+                let key = (normalize_title(&item.series_name), item.parent_index_number, item.index_number);
+                index.by_episode.entry(key).or_default().push(item);
+            }
+            let key = (item.type_field.clone(), normalize_title(&item.name), item.production_year);
+            index.by_title.entry(key).or_default().push(item);
+        }
+        index
+    }
+
+    ///
+    /// Finds the item for a watch state entry by its provider ids, falling back to the series, season and
+    /// episode number for episodes or the name and year for anything else.
+    ///
+    fn find(&self, entry: &WatchStateItem) -> ItemMatch<'a> {
+        let mut candidates: Vec<&MediaItem> = Vec::new();
+        for (provider, id) in &entry.provider_ids {
+            for item in self.by_provider.get(&provider_key(&entry.item_type, provider, id)).into_iter().flatten() {
+                if !candidates.iter().any(|c| c.id == item.id) {
+                    candidates.push(item);
+                }
+            }
+        }
+        if candidates.is_empty() {
+            let fallback = match (&entry.series_name, entry.season, entry.episode) {
+                (Some(series), Some(season), Some(episode)) => self.by_episode.get(&(normalize_title(series), season, episode)),
+                _ => self.by_title.get(&(entry.item_type.clone(), normalize_title(&entry.name), entry.year.unwrap_or_default())),
+            };
+            candidates = fallback.cloned().unwrap_or_default();
+        }
+        match candidates.len() {
+            0 => ItemMatch::Missing,
+            1 => ItemMatch::Found(candidates[0]),
+            n => ItemMatch::Ambiguous(n),
+        }
+    }
+}
+
+///
+/// Resolves the items a played or progress command applies to.  Items given by id are used as they are; otherwise
+/// the movies and episodes matching the search, library and series are gathered through the item search.
+///
+fn select_items(cfg: &AppConfig, selector: &ItemSelector) -> Result<Vec<MediaItem>, String> {
+    if !selector.ids.is_empty() {
+        return Ok(selector.ids.iter().map(|id| MediaItem { id: id.clone(), name: id.clone(), ..MediaItem::default() }).collect());
+    }
+    let mut parent_id = String::new();
+    if let Some(library) = &selector.parent {
+        let libraries = get_libraries(ServerInfo::new("/Library/VirtualFolders", &cfg.server_url, &cfg.api_key))
+            .map_err(|e| format!("Unable to get libraries, {e}"))?;
+        parent_id = libraries
+            .into_iter()
+            .find(|l| l.name.eq_ignore_ascii_case(library))
+            .map(|l| l.item_id)
+            .ok_or_else(|| format!("Could not find library {library}."))?;
+    }
+    let mut item_types = PLAYABLE_TYPES;
+    if let Some(name) = &selector.series {
+        let series = execute_search(name, "Series", &parent_id, false, cfg).items;
+        // The search also returns partial matches, which must never be acted on without the user naming them.
+        let exact: Vec<&MediaItem> = series.iter().filter(|s| s.name.eq_ignore_ascii_case(name)).collect();
+        let describe_series = |found: &[&MediaItem]| -> String {
+            found.iter().map(|s| format!("{} ({}, id {})", s.name, s.production_year, s.id)).collect::<Vec<String>>().join(", ")
+        };
+        parent_id = match exact.as_slice() {
+            [only] => only.id.clone(),
+            [] if series.is_empty() => return Err(format!("Could not find series {name}.")),
+            [] => {
+                let candidates: Vec<&MediaItem> = series.iter().collect();
+                return Err(format!("No series is named exactly {name}.  Did you mean: {}?", describe_series(&candidates)));
+            }
+            found => return Err(format!("More than one series is named {name}: {}.  Use --id instead.", describe_series(found))),
+        };
+        item_types = "Episode";
+    }
+
+    let items = execute_search(selector.search.as_deref().unwrap_or_default(), item_types, &parent_id, false, cfg).items;
+    if items.is_empty() {
+        return Err("No items matched the selection.".to_owned());
+    }
+    Ok(items)
+}
+
+///
+/// Applies an action to each item, reporting every item and a summary.  Exits with 1 if any item failed.
+///
+fn run_for_items<F>(items: &[MediaItem], done: &str, dry_run: bool, action: F)
+where
+    F: Fn(&MediaItem) -> Result<(), String>,
+{
+    let (mut succeeded, mut failed) = (0, 0);
+    for item in items {
+        let name = describe(&WatchStateItem::from_media_item(item));
+        if dry_run {
+            println!("  {name} would be {done}.");
+            succeeded += 1;
+            continue;
+        }
+        match action(item) {
+            Ok(()) => {
+                println!("  {name} {done}.");
+                succeeded += 1;
+            }
+            Err(e) => {
+                eprintln!("[ERROR] Unable to update {name}.  {e}");
+                failed += 1;
+            }
+        }
+    }
+    if dry_run {
+        println!("{succeeded} item(s) would be {done}.");
+    } else {
+        println!("{succeeded} item(s) {done}, {failed} failed.");
+    }
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn provider_key(item_type: &str, provider: &str, id: &str) -> String {
+    format!("{item_type}:{}:{}", provider.to_lowercase(), id.to_lowercase())
+}
+
+// Titles are compared without case or punctuation, as other services rarely spell them exactly like Jellyfin.
+fn normalize_title(title: &str) -> String {
+    title.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+fn history_key(entry: &WatchStateItem) -> String {
+    format!(
+        "{}|{}|{:?}|{:?}|{}|{:?}",
+        entry.item_type,
+        normalize_title(entry.series_name.as_deref().unwrap_or_default()),
+        entry.season,
+        entry.episode,
+        normalize_title(&entry.name),
+        entry.year
+    )
+}
+
+///
+/// Gathers the movies, series and episodes of the server with the user's data, optionally limited by
+/// one of the item filters such as IsPlayed.
+///
+fn user_items(cfg: &AppConfig, user_id: &str, filter: Option<&str>) -> Result<Vec<MediaItem>, String> {
+    let mut query = vec![
+        ("userId", user_id),
+        ("Recursive", "true"),
+        ("IncludeItemTypes", WATCHED_TYPES),
+        ("Fields", "ProviderIds"),
+    ];
+    if let Some(filter) = filter {
+        query.push(("Filters", filter));
+    }
+    get_search_results(ServerInfo::new("/Items", &cfg.server_url, &cfg.api_key), query)
+        .map(|media| media.items)
+        .map_err(|e| format!("Unable to gather items, {e}"))
+}
+
+///
+/// Matches watch state entries against the server and applies them to the user.  Existing state is only
+/// ever added to: items stay played, play counts and positions only grow and favorites are kept.
+///
+fn apply_watch_state(cfg: &AppConfig, user: &UserDetails, entries: &[WatchStateItem], dry_run: bool) {
+    let library = exit_on_error(user_items(cfg, &user.id, None));
+    let index = ItemIndex::new(&library);
+    let server_info = ServerInfo::new(USER_ITEM_DATA, &cfg.server_url, &cfg.api_key);
+
+    let (mut updated, mut unchanged, mut failed) = (0, 0, 0);
+    let mut ambiguous: Vec<String> = Vec::new();
+    let mut unmatched: Vec<String> = Vec::new();
+    for entry in entries {
+        let item = match index.find(entry) {
+            ItemMatch::Found(item) => item,
+            ItemMatch::Ambiguous(count) => {
+                ambiguous.push(format!("{} ({count} matches)", describe(entry)));
+                continue;
+            }
+            ItemMatch::Missing => {
+                unmatched.push(describe(entry));
+                continue;
+            }
+        };
+        let Some(data) = merge_user_data(entry, item) else {
+            unchanged += 1;
+            continue;
+        };
+        if dry_run {
+            println!("  {} would be updated.", describe(entry));
+            updated += 1;
+        } else {
+            match update_user_item_data(&server_info, &item.id, &user.id, &data) {
+                Ok(()) => {
+                    println!("  {} updated.", describe(entry));
+                    updated += 1;
+                }
+                Err(e) => {
+                    eprintln!("[ERROR] Unable to update {}.  {e}", describe(entry));
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    let verb = if dry_run { "would be updated" } else { "updated" };
+    println!(
+        "{} matched ({updated} {verb}, {unchanged} already up to date), {} ambiguous, {} unmatched, {failed} failed.",
+        updated + unchanged + failed,
+        ambiguous.len(),
+        unmatched.len()
+    );
+    for (heading, items) in [("Ambiguous", &ambiguous), ("Unmatched", &unmatched)] {
+        if !items.is_empty() {
+            println!("\n{heading}:");
+            for item in items {
+                println!("  - {item}");
+            }
+        }
+    }
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+///
+/// Builds the changes needed to bring an item up to the exported state, or None when it already is.
+///
+fn merge_user_data(entry: &WatchStateItem, item: &MediaItem) -> Option<UserItemData> {
+    let current = &item.user_data;
+    let mut data = UserItemData::default();
+    if entry.played && !current.played {
+        data.played = Some(true);
+    }
+    if entry.play_count > current.play_count {
+        data.play_count = Some(entry.play_count);
+    }
+    if entry.playback_position_ticks > current.playback_position_ticks && !(entry.played || current.played) {
+        data.playback_position_ticks = Some(entry.playback_position_ticks);
+    }
+    if entry.is_favorite && !current.is_favorite {
+        data.is_favorite = Some(true);
+    }
+    if let Some(date) = parse_jellyfin_date(entry.last_played_date.as_deref())
+        && parse_jellyfin_date(Some(&current.last_played_date)).is_none_or(|current| date > current)
+    {
+        data.last_played_date = Some(date.to_rfc3339_opts(SecondsFormat::Secs, true));
+    }
+
+    let unchanged = data.played.is_none()
+        && data.play_count.is_none()
+        && data.playback_position_ticks.is_none()
+        && data.is_favorite.is_none()
+        && data.last_played_date.is_none();
+    (!unchanged).then_some(data)
+}
+
+fn describe(entry: &WatchStateItem) -> String {
+    match (&entry.series_name, entry.season, entry.episode) {
+        (Some(series), Some(season), Some(episode)) => format!("{series} S{season:02}E{episode:02} {}", entry.name),
+        _ => match entry.year {
+            Some(year) => format!("{} ({year})", entry.name),
+            None => entry.name.clone(),
+        },
+    }
+}
@@ -0,0 +1,98 @@
+use comfy_table::{ContentArrangement, Table};
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+// One row of the users audit report.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserAudit {
+    pub username: String,
+    pub admin: bool,
+    pub disabled: bool,
+    pub has_password: bool,
+    pub remote_access: bool,
+    pub last_login: String,
+    pub last_activity: String,
+    pub days_inactive: Option<i64>,
+    pub invalid_login_attempts: i64,
+    pub failed_logins_in_log: i64,
+    pub flags: Vec<String>,
+}
+
+impl UserAudit {
+    pub fn csv_print(rows: &[UserAudit]) {
+        let mut writer = csv::Writer::from_writer(std::io::stdout());
+        writer
+            .write_record([
+                "username",
+                "admin",
+                "disabled",
+                "has_password",
+                "remote_access",
+                "last_login",
+                "last_activity",
+                "days_inactive",
+                "invalid_login_attempts",
+                "failed_logins_in_log",
+                "flags",
+            ])
+            .expect("Unable to write report.");
+        for row in rows {
+            writer
+                .write_record([
+                    row.username.clone(),
+                    row.admin.to_string(),
+                    row.disabled.to_string(),
+                    row.has_password.to_string(),
+                    row.remote_access.to_string(),
+                    row.last_login.clone(),
+                    row.last_activity.clone(),
+                    row.days_inactive.map(|d| d.to_string()).unwrap_or_default(),
+                    row.invalid_login_attempts.to_string(),
+                    row.failed_logins_in_log.to_string(),
+                    row.flags.join(";"),
+                ])
+                .expect("Unable to write report.");
+        }
+        writer.flush().expect("Unable to write report.");
+    }
+
+    pub fn json_print(rows: &[UserAudit]) {
+        println!("{}", serde_json::to_string_pretty(&rows).unwrap());
+    }
+
+    pub fn table_print(rows: Vec<UserAudit>) {
+        let yes_no = |b: bool| if b { "Yes" } else { "No" }.to_owned();
+        let mut table = Table::new();
+        table
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_width(160)
+            .set_header(vec![
+                "User",
+                "Admin",
+                "Disabled",
+                "Password",
+                "Remote",
+                "Last Login",
+                "Last Activity",
+                "Days Inactive",
+                "Failed Logins",
+                "Flags",
+            ]);
+        for row in rows {
+            table.add_row(vec![
+                row.username,
+                yes_no(row.admin),
+                yes_no(row.disabled),
+                yes_no(row.has_password),
+                yes_no(row.remote_access),
+                row.last_login,
+                row.last_activity,
+                row.days_inactive.map(|d| d.to_string()).unwrap_or_default(),
+                format!("{} ({} in log)", row.invalid_login_attempts, row.failed_logins_in_log),
+                row.flags.join(", "),
+            ]);
+        }
+        println!("{table}");
+    }
+}
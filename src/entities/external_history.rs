@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use chrono::{DateTime, NaiveDate, NaiveTime, SecondsFormat, Utc};
+use serde_derive::Deserialize;
+
+use super::watch_state::WatchStateItem;
+
+/// A row of a Trakt watch history export.  Episode rows carry the show in `title` and `year` and the
+/// episode in the `episode_` columns.
+#[derive(Debug, Deserialize)]
+struct TraktRow {
+    #[serde(default, alias = "media_type")]
+    r#type: Option<String>,
+    title: String,
+    #[serde(default)]
+    year: Option<i64>,
+    #[serde(default, alias = "last_watched_at", alias = "date")]
+    watched_at: Option<String>,
+    #[serde(default, alias = "plays")]
+    play_count: Option<i64>,
+    #[serde(default, alias = "imdb")]
+    imdb_id: Option<String>,
+    #[serde(default, alias = "tmdb")]
+    tmdb_id: Option<String>,
+    #[serde(default, alias = "tvdb")]
+    tvdb_id: Option<String>,
+    #[serde(default)]
+    episode_title: Option<String>,
+    #[serde(default, alias = "season")]
+    season_number: Option<i64>,
+    #[serde(default, alias = "episode")]
+    episode_number: Option<i64>,
+    #[serde(default)]
+    episode_imdb_id: Option<String>,
+    #[serde(default)]
+    episode_tmdb_id: Option<String>,
+    #[serde(default)]
+    episode_tvdb_id: Option<String>,
+}
+
+/// A row of a Letterboxd `diary.csv` or `watched.csv` export.  Letterboxd only knows films and does not
+/// export provider ids.
+#[derive(Debug, Deserialize)]
+struct LetterboxdRow {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Year", default)]
+    year: Option<i64>,
+    #[serde(rename = "Date", default)]
+    date: Option<String>,
+    #[serde(rename = "Watched Date", default)]
+    watched_date: Option<String>,
+}
+
+impl TryFrom<TraktRow> for WatchStateItem {
+    type Error = String;
+
+    fn try_from(row: TraktRow) -> Result<Self, Self::Error> {
+        let is_episode = match row.r#type.as_deref().map(str::to_lowercase).as_deref() {
+            Some("movie") => false,
+            Some("episode") => true,
+            None | Some("") => row.season_number.is_some() && row.episode_number.is_some(),
+            Some(other) => return Err(format!("{}: unsupported type \"{other}\"", row.title)),
+        };
+        let (item_type, ids) = if is_episode {
+            ("Episode", [row.episode_imdb_id, row.episode_tmdb_id, row.episode_tvdb_id])
+        } else {
+            ("Movie", [row.imdb_id, row.tmdb_id, row.tvdb_id])
+        };
+        let provider_ids = ["Imdb", "Tmdb", "Tvdb"]
+            .into_iter()
+            .zip(ids)
+            .filter_map(|(provider, id)| Some((provider.to_owned(), id.filter(|id| !id.is_empty())?)))
+            .collect::<BTreeMap<String, String>>();
+
+        Ok(WatchStateItem {
+            name: if is_episode { row.episode_title.unwrap_or_default() } else { row.title.clone() },
+            item_type: item_type.to_owned(),
+            year: row.year,
+            series_name: is_episode.then_some(row.title.clone()),
+            season: row.season_number.filter(|_| is_episode),
+            episode: row.episode_number.filter(|_| is_episode),
+            provider_ids,
+            played: true,
+            play_count: row.play_count.unwrap_or(1).max(1),
+            last_played_date: watched_date(row.watched_at.as_deref()).map_err(|e| format!("{}: {e}", row.title))?,
+            ..WatchStateItem::default()
+        })
+    }
+}
+
+impl TryFrom<LetterboxdRow> for WatchStateItem {
+    type Error = String;
+
+    fn try_from(row: LetterboxdRow) -> Result<Self, Self::Error> {
+        // The diary has both the day it was logged and the day it was watched; watched.csv only the former.
+        let date = row.watched_date.filter(|d| !d.is_empty()).or(row.date);
+        Ok(WatchStateItem {
+            last_played_date: watched_date(date.as_deref()).map_err(|e| format!("{}: {e}", row.name))?,
+            name: row.name,
+            item_type: "Movie".to_owned(),
+            year: row.year,
+            played: true,
+            play_count: 1,
+            ..WatchStateItem::default()
+        })
+    }
+}
+
+///
+/// Reads a Trakt watch history export.  Rows that cannot be parsed are returned as errors so the caller can
+/// report them individually.
+///
+pub fn read_trakt(path: &str) -> Result<Vec<Result<WatchStateItem, String>>, String> {
+    read_rows::<TraktRow>(path, "title", "Trakt")
+}
+
+///
+/// Reads a Letterboxd diary or watched export.  Rows that cannot be parsed are returned as errors so the caller
+/// can report them individually.
+///
+pub fn read_letterboxd(path: &str) -> Result<Vec<Result<WatchStateItem, String>>, String> {
+    read_rows::<LetterboxdRow>(path, "Name", "Letterboxd")
+}
+
+/*
+    The following section contains additional
+    functions that are used to support the external_history
+    base functions.
+*/
+
+fn read_rows<T>(path: &str, required: &str, source: &str) -> Result<Vec<Result<WatchStateItem, String>>, String>
+where
+    T: serde::de::DeserializeOwned,
+    WatchStateItem: TryFrom<T, Error = String>,
+{
+    let data = fs::read_to_string(path).map_err(|e| format!("Unable to read {path}, {e}"))?;
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(data.trim_start_matches('\u{feff}').as_bytes());
+    let headers = reader.headers().map_err(|e| format!("Unable to parse {path}, {e}"))?;
+    if !headers.iter().any(|h| h == required) {
+        return Err(format!("{path} does not look like a {source} export, it has no \"{required}\" column."));
+    }
+    Ok(reader
+        .deserialize::<T>()
+        .enumerate()
+        .map(|(index, row)| {
+            let row = row.map_err(|e| format!("Row {}: {e}", index + 1))?;
+            WatchStateItem::try_from(row).map_err(|e| format!("Row {}: {e}", index + 1))
+        })
+        .collect())
+}
+
+///
+/// Normalizes the date an item was watched, which exports give either as a timestamp or as a day.
+///
+fn watched_date(date: Option<&str>) -> Result<Option<String>, String> {
+    let Some(date) = date.filter(|d| !d.is_empty()) else {
+        return Ok(None);
+    };
+    let parsed = DateTime::parse_from_rfc3339(date)
+        .map(|d| d.with_timezone(&Utc))
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y-%m-%d").map(|d| d.and_time(NaiveTime::MIN).and_utc()))
+        .map_err(|_| format!("unrecognised date \"{date}\""))?;
+    Ok(Some(parsed.to_rfc3339_opts(SecondsFormat::Secs, true)))
+}
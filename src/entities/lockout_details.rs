@@ -0,0 +1,56 @@
+use comfy_table::{ContentArrangement, Table};
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+// One row of `users locked`, a user that is locked out or about to be.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockoutStatus {
+    pub username: String,
+    pub status: String,
+    pub failed_attempts: i64,
+    pub lockout_threshold: Option<i64>,
+    pub disabled: bool,
+}
+
+impl LockoutStatus {
+    pub fn csv_print(rows: &[LockoutStatus]) {
+        let mut writer = csv::Writer::from_writer(std::io::stdout());
+        writer
+            .write_record(["username", "status", "failed_attempts", "lockout_threshold", "disabled"])
+            .expect("Unable to write report.");
+        for row in rows {
+            writer
+                .write_record([
+                    row.username.clone(),
+                    row.status.clone(),
+                    row.failed_attempts.to_string(),
+                    row.lockout_threshold.map(|n| n.to_string()).unwrap_or_default(),
+                    row.disabled.to_string(),
+                ])
+                .expect("Unable to write report.");
+        }
+        writer.flush().expect("Unable to write report.");
+    }
+
+    pub fn json_print(rows: &[LockoutStatus]) {
+        println!("{}", serde_json::to_string_pretty(&rows).unwrap());
+    }
+
+    pub fn table_print(rows: Vec<LockoutStatus>) {
+        let mut table = Table::new();
+        table
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_width(120)
+            .set_header(vec!["User", "Status", "Failed Logins", "Lockout After", "Disabled"]);
+        for row in rows {
+            table.add_row(vec![
+                row.username,
+                row.status,
+                row.failed_attempts.to_string(),
+                row.lockout_threshold.map_or_else(|| "never".to_owned(), |n| n.to_string()),
+                if row.disabled { "Yes" } else { "No" }.to_owned(),
+            ]);
+        }
+        println!("{table}");
+    }
+}
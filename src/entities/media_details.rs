@@ -4,6 +4,8 @@ Reasoning behind this is Jellyfin's GetItem API information.
 https://api.jellyfin.org/#tag/Items/operation/GetItems
 */
 
+use std::collections::BTreeMap;
+
 use comfy_table::{ContentArrangement, Table};
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
@@ -584,11 +586,12 @@ pub struct RemoteTrailer {
     pub name: String,
 }
 
+// Ids of the item in external databases, keyed by provider name such as "Imdb", "Tmdb" or "Tvdb".
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase", default)]
+#[serde(default)]
 pub struct ProviderIds {
-    pub property1: String,
-    pub property2: String,
+    #[serde(flatten)]
+    pub ids: BTreeMap<String, String>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -752,9 +755,9 @@ pub struct GenreItem {
 #[serde(rename_all = "camelCase", default)]
 pub struct UserData {
     #[serde(rename = "Rating")]
-    pub rating: i64,
+    pub rating: f64,
     #[serde(rename = "PlayedPercentage")]
-    pub played_percentage: i64,
+    pub played_percentage: f64,
     #[serde(rename = "UnplayedItemCount")]
     pub unplayed_item_count: i64,
     #[serde(rename = "PlaybackPositionTicks")]
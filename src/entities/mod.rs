@@ -1,17 +1,25 @@
 pub mod activity_details;
+pub mod audit_details;
 pub mod backup_details;
 pub mod device_details;
+pub mod external_history;
 pub mod library_details;
 pub mod library_options;
+pub mod lockout_details;
 pub mod log_details;
 pub mod media_details;
 pub mod movie_details;
 pub mod package_details;
+pub mod parental_details;
 pub mod plugin_details;
+pub mod prune_journal;
 pub mod repository_details;
 pub mod server_info;
 pub mod task_details;
 pub mod token_details;
 pub mod user_details;
+pub mod user_import;
+pub mod user_manifest;
 pub mod quickconnect_details;
-pub mod version;
\ No newline at end of file
+pub mod version;
+pub mod watch_state;
\ No newline at end of file
@@ -0,0 +1,64 @@
+use comfy_table::{ContentArrangement, Table};
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParentalRating {
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "Value", default)]
+    pub value: Option<i64>,
+}
+
+// One row of the parental report, the restrictions that apply to a single user.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParentalRestrictions {
+    pub username: String,
+    pub max_rating: String,
+    pub blocked_tags: Vec<String>,
+    pub blocked_unrated: Vec<String>,
+    pub schedules: Vec<String>,
+}
+
+impl ParentalRestrictions {
+    pub fn csv_print(rows: &[ParentalRestrictions]) {
+        let mut writer = csv::Writer::from_writer(std::io::stdout());
+        writer
+            .write_record(["username", "max_rating", "blocked_tags", "blocked_unrated", "schedules"])
+            .expect("Unable to write report.");
+        for row in rows {
+            writer
+                .write_record([
+                    row.username.as_str(),
+                    row.max_rating.as_str(),
+                    row.blocked_tags.join(";").as_str(),
+                    row.blocked_unrated.join(";").as_str(),
+                    row.schedules.join(";").as_str(),
+                ])
+                .expect("Unable to write report.");
+        }
+        writer.flush().expect("Unable to write report.");
+    }
+
+    pub fn json_print(rows: &[ParentalRestrictions]) {
+        println!("{}", serde_json::to_string_pretty(&rows).unwrap());
+    }
+
+    pub fn table_print(rows: Vec<ParentalRestrictions>) {
+        let mut table = Table::new();
+        table
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_width(120)
+            .set_header(vec!["User", "Max Rating", "Blocked Tags", "Blocked Unrated", "Schedules"]);
+        for row in rows {
+            table.add_row(vec![
+                row.username,
+                row.max_rating,
+                row.blocked_tags.join(", "),
+                row.blocked_unrated.join(", "),
+                row.schedules.join(", "),
+            ]);
+        }
+        println!("{table}");
+    }
+}
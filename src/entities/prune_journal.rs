@@ -0,0 +1,46 @@
+use std::fs;
+use std::path::Path;
+
+use serde_derive::{Deserialize, Serialize};
+
+/// Record of the accounts changed by `users prune`, used by `users restore`.
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct PruneJournal {
+    pub entries: Vec<PruneEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PruneEntry {
+    pub date: String,
+    pub action: PruneAction,
+    pub username: String,
+    pub user_id: String,
+    pub last_activity: String,
+    #[serde(default)]
+    pub restored: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PruneAction {
+    Disabled,
+    Deleted,
+}
+
+impl PruneJournal {
+    ///
+    /// Reads the journal, treating a missing file as an empty journal.
+    ///
+    pub fn load(path: &Path) -> Result<PruneJournal, String> {
+        if !path.exists() {
+            return Ok(PruneJournal::default());
+        }
+        let data = fs::read_to_string(path).map_err(|e| format!("Unable to read {}, {e}", path.display()))?;
+        serde_json::from_str(&data).map_err(|e| format!("Unable to parse {}, {e}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let data = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, data).map_err(|e| format!("Unable to write {}, {e}", path.display()))
+    }
+}
@@ -1,6 +1,8 @@
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
 
+use comfy_table::{ContentArrangement, Table};
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TokenDetails {
@@ -40,3 +42,45 @@ pub struct TokenItem {
     #[serde(rename = "UserName", default)]
     pub user_name: String,
 }
+
+impl TokenItem {
+    pub fn csv_print(tokens: &[TokenItem]) {
+        for token in tokens {
+            println!("{}, {}, {}, {}",
+                token.app_name,
+                token.access_token,
+                token.date_created,
+                token.date_last_activity
+            );
+        }
+    }
+
+    pub fn json_print(tokens: &[TokenItem]) {
+        println!("{}", serde_json::to_string_pretty(&tokens).unwrap());
+    }
+
+    pub fn table_print(tokens: Vec<TokenItem>, current_key: &str) {
+        let mut table = Table::new();
+        table
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_width(120)
+            .set_header(vec![
+                "App Name",
+                "Access Token",
+                "Date Created",
+                "Last Used",
+                "In Use",
+            ]);
+        for token in tokens {
+            let in_use = if token.access_token == current_key { "*" } else { "" };
+            table.add_row(vec![
+                token.app_name,
+                token.access_token,
+                token.date_created,
+                token.date_last_activity,
+                in_use.to_owned(),
+            ]);
+        }
+        println!("{table}");
+    }
+}
@@ -1,5 +1,5 @@
 #[allow(clippy::struct_excessive_bools)]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserDetails {
     #[serde(rename = "Name")]
     pub name: String,
@@ -33,7 +33,7 @@ pub struct UserDetails {
 
 // Struct to contain the Policy information that is a part of the user details.
 #[allow(clippy::struct_excessive_bools)]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Policy {
     #[serde(rename = "IsAdministrator")]
     pub is_administrator: bool,
@@ -41,8 +41,9 @@ pub struct Policy {
     pub is_hidden: bool,
     #[serde(rename = "IsDisabled")]
     pub is_disabled: bool,
+    // Jellyfin leaves this out when the user has no rating limit, which has to be sent back as null rather than 0.
     #[serde(rename = "MaxParentalRating", default)]
-    pub max_parental_rating: i64,
+    pub max_parental_rating: Option<i64>,
     #[serde(rename = "BlockedTags")]
     pub blocked_tags: Vec<String>,
     #[serde(rename = "EnableUserPreferenceAccess")]
@@ -113,10 +114,33 @@ pub struct Policy {
     pub password_reset_provider_id: String,
     #[serde(rename = "SyncPlayAccess")]
     pub sync_play_access: String,
+    // Policy settings added by newer servers, kept so that saving the policy does not reset them.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Policy {
+    ///
+    /// Number of failed logins after which Jellyfin disables the account, or None when lockout is turned off.
+    /// Zero falls back to the server defaults of five attempts for administrators and three for everyone else.
+    ///
+    pub fn lockout_threshold(&self) -> Option<i64> {
+        match self.login_attempts_before_lockout {
+            0 if self.is_administrator => Some(5),
+            0 => Some(3),
+            n if n < 0 => None,
+            n => Some(n),
+        }
+    }
+
+    /// Whether the account was disabled by too many failed logins.
+    pub fn is_locked_out(&self) -> bool {
+        self.is_disabled && self.lockout_threshold().is_some_and(|n| self.invalid_login_attempt_count >= n)
+    }
 }
 
 #[allow(clippy::struct_excessive_bools)]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Configuration {
     #[serde(rename = "AudioLanguagePreference", default)]
     pub audio_language_preference: String,
@@ -148,18 +172,71 @@ pub struct Configuration {
     pub remember_subtitle_selections: bool,
     #[serde(rename = "EnableNextEpisodeAutoPlay")]
     pub enable_next_episode_auto_play: bool,
+    // Preferences added by newer servers, kept so that saving the configuration does not reset them.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct AccessSchedule {
     #[serde(rename = "UserId")]
     pub user_id: String,
     #[serde(rename = "DayOfWeek")]
     pub day_of_week: String,
     #[serde(rename = "StartHour")]
-    pub start_hour: i64,
+    pub start_hour: f64,
     #[serde(rename = "EndHour")]
-    pub end_hour: i64,
+    pub end_hour: f64,
+}
+
+impl AccessSchedule {
+    /// Days covered by the schedule, with Everyday, Weekday and Weekend expanded.
+    pub fn days(&self) -> Vec<&str> {
+        match self.day_of_week.as_str() {
+            "Everyday" => vec!["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"],
+            "Weekday" => vec!["Monday", "Tuesday", "Wednesday", "Thursday", "Friday"],
+            "Weekend" => vec!["Saturday", "Sunday"],
+            day => vec![day],
+        }
+    }
+
+    /// Whether both schedules cover the same hour on any day.
+    pub fn overlaps(&self, other: &AccessSchedule) -> bool {
+        let days = self.days();
+        other.days().iter().any(|d| days.contains(d))
+            && self.start_hour < other.end_hour
+            && other.start_hour < self.end_hour
+    }
+
+    /// Checks that the hours are within a day and the schedule ends after it starts.
+    pub fn validate(&self) -> Result<(), String> {
+        let finite = self.start_hour.is_finite() && self.end_hour.is_finite();
+        if !finite || self.start_hour < 0.0 || self.end_hour > 24.0 || self.start_hour >= self.end_hour {
+            return Err(format!(
+                "Schedule {self} must have hours between 0 and 24 with the start before the end."
+            ));
+        }
+        Ok(())
+    }
+
+    ///
+    /// Validates each schedule and rejects any that overlap another.
+    ///
+    pub fn validate_all(schedules: &[AccessSchedule]) -> Result<(), String> {
+        for (index, schedule) in schedules.iter().enumerate() {
+            schedule.validate()?;
+            if let Some(other) = schedules[..index].iter().find(|o| o.overlaps(schedule)) {
+                return Err(format!("Schedule {schedule} overlaps {other}."));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for AccessSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {}-{}", self.day_of_week, self.start_hour, self.end_hour)
+    }
 }
 impl UserDetails {
     pub fn json_print_user(user: &UserDetails) {
@@ -0,0 +1,133 @@
+use std::fs;
+use std::path::Path;
+
+use serde_derive::Deserialize;
+
+/// A single user to be created by `add-users`.  Only the username is required; any policy values left out keep
+/// the server defaults.
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct UserImport {
+    #[serde(alias = "name")]
+    pub username: String,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub admin: Option<bool>,
+    #[serde(default)]
+    pub disabled: Option<bool>,
+    #[serde(default)]
+    pub hidden: Option<bool>,
+    /// Names of the libraries the user may access.  When supplied, access to all other libraries is removed.
+    #[serde(default)]
+    pub libraries: Option<Vec<String>>,
+    #[serde(default)]
+    pub max_parental_rating: Option<i64>,
+    #[serde(default)]
+    pub remote_access: Option<bool>,
+}
+
+// CSV cannot hold a list in a single column, so libraries are separated by ';' instead.
+#[derive(Debug, Deserialize)]
+struct UserImportCsv {
+    #[serde(alias = "name")]
+    username: String,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    admin: Option<bool>,
+    #[serde(default)]
+    disabled: Option<bool>,
+    #[serde(default)]
+    hidden: Option<bool>,
+    #[serde(default)]
+    libraries: Option<String>,
+    #[serde(default)]
+    max_parental_rating: Option<i64>,
+    #[serde(default)]
+    remote_access: Option<bool>,
+}
+
+impl From<UserImportCsv> for UserImport {
+    fn from(row: UserImportCsv) -> Self {
+        UserImport {
+            username: row.username,
+            password: row.password.filter(|p| !p.is_empty()),
+            admin: row.admin,
+            disabled: row.disabled,
+            hidden: row.hidden,
+            libraries: row.libraries.map(|l| {
+                l.split(';')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_owned)
+                    .collect()
+            }),
+            max_parental_rating: row.max_parental_rating,
+            remote_access: row.remote_access,
+        }
+    }
+}
+
+impl UserImport {
+    ///
+    /// Reads the users from a JSON array or a CSV file.  CSV files with a header row containing "username" may
+    /// use any of the policy columns; files without a header are read as the original "username,password" lines.
+    /// Rows that cannot be parsed are returned as errors so the caller can report them individually.
+    ///
+    pub fn read_file(path: &str) -> Result<Vec<Result<UserImport, String>>, String> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Unable to read {path}, {e}"))?;
+        let is_json = Path::new(path).extension().is_some_and(|e| e.eq_ignore_ascii_case("json"))
+            || data.trim_start().starts_with('[');
+        if is_json {
+            let users: Vec<UserImport> =
+                serde_json::from_str(&data).map_err(|e| format!("Unable to parse {path}, {e}"))?;
+            return Ok(users.into_iter().map(Ok).collect());
+        }
+
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .flexible(true)
+            .trim(csv::Trim::Headers)
+            .from_reader(data.as_bytes());
+        let has_header = reader
+            .headers()
+            .map_err(|e| format!("Unable to parse {path}, {e}"))?
+            .iter()
+            .any(|h| h == "username" || h == "name");
+
+        if has_header {
+            return Ok(reader
+                .deserialize::<UserImportCsv>()
+                .map(|row| row.map(UserImport::from).map_err(|e| e.to_string()))
+                .collect());
+        }
+
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(data.as_bytes());
+        Ok(reader
+            .records()
+            .map(|record| {
+                let record = record.map_err(|e| e.to_string())?;
+                // Anything after the first comma is the password, which may itself contain commas.
+                let password = record.iter().skip(1).collect::<Vec<&str>>().join(",");
+                Ok(UserImport {
+                    username: record.get(0).unwrap_or_default().to_owned(),
+                    password: Some(password).filter(|p| !p.is_empty()),
+                    ..UserImport::default()
+                })
+            })
+            .collect())
+    }
+
+    /// Whether the row sets anything beyond the username and password.
+    pub fn has_policy(&self) -> bool {
+        self.admin.is_some()
+            || self.disabled.is_some()
+            || self.hidden.is_some()
+            || self.libraries.is_some()
+            || self.max_parental_rating.is_some()
+            || self.remote_access.is_some()
+    }
+}
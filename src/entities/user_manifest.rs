@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+use std::fs;
+
+use serde_derive::Deserialize;
+
+use super::user_details::AccessSchedule;
+
+const SCHEDULE_DAYS: [&str; 10] = [
+    "Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Everyday", "Weekday", "Weekend",
+];
+
+/// Desired state of the server's users as read by `users plan` and `users apply`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserManifest {
+    pub users: Vec<ManifestUser>,
+}
+
+/// A managed user.  Settings that are left out are not managed and keep whatever value the server has.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestUser {
+    pub name: String,
+    /// Only used when the user is created.
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub admin: Option<bool>,
+    #[serde(default)]
+    pub disabled: Option<bool>,
+    #[serde(default)]
+    pub hidden: Option<bool>,
+    /// Names of the libraries the user may access, or "*" for all libraries.
+    #[serde(default)]
+    pub libraries: Option<Vec<String>>,
+    #[serde(default)]
+    pub max_parental_rating: Option<i64>,
+    #[serde(default)]
+    pub remote_access: Option<bool>,
+    #[serde(default)]
+    pub schedules: Option<Vec<ManifestSchedule>>,
+}
+
+/// Window during which the user is allowed to sign in.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestSchedule {
+    pub day: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+impl ManifestSchedule {
+    pub fn to_access_schedule(&self, user_id: &str) -> AccessSchedule {
+        AccessSchedule {
+            user_id: user_id.to_owned(),
+            day_of_week: self.day.clone(),
+            start_hour: self.start,
+            end_hour: self.end,
+        }
+    }
+}
+
+impl UserManifest {
+    ///
+    /// Reads and validates a YAML user manifest.
+    ///
+    pub fn read_file(path: &str) -> Result<UserManifest, String> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Unable to read {path}, {e}"))?;
+        let manifest: UserManifest =
+            serde_norway::from_str(&data).map_err(|e| format!("Unable to parse {path}, {e}"))?;
+
+        let mut names = HashSet::new();
+        for user in &manifest.users {
+            if user.name.is_empty() {
+                return Err("Every user in the manifest needs a name.".to_owned());
+            }
+            if !names.insert(user.name.to_lowercase()) {
+                return Err(format!("User \"{}\" is listed more than once.", user.name));
+            }
+            for schedule in user.schedules.iter().flatten() {
+                if !SCHEDULE_DAYS.contains(&schedule.day.as_str()) {
+                    return Err(format!(
+                        "Schedule day \"{}\" for user \"{}\" must be one of {}.",
+                        schedule.day,
+                        user.name,
+                        SCHEDULE_DAYS.join(", ")
+                    ));
+                }
+            }
+            if let Some(schedules) = &user.schedules {
+                let schedules: Vec<AccessSchedule> = schedules.iter().map(|s| s.to_access_schedule("")).collect();
+                AccessSchedule::validate_all(&schedules).map_err(|e| format!("User \"{}\": {e}", user.name))?;
+            }
+        }
+        Ok(manifest)
+    }
+}
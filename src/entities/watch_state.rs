@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use serde_derive::{Deserialize, Serialize};
+
+use super::media_details::MediaItem;
+
+/// Watch state of a user as written by `watch-state export`.
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct WatchStateExport {
+    pub user: String,
+    pub server: String,
+    pub exported: String,
+    pub items: Vec<WatchStateItem>,
+}
+
+/// An item the user has played, started or marked as a favorite, along with what is needed to find it on
+/// another server.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchStateItem {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub item_type: String,
+    pub year: Option<i64>,
+    pub series_name: Option<String>,
+    pub season: Option<i64>,
+    pub episode: Option<i64>,
+    pub provider_ids: BTreeMap<String, String>,
+    pub played: bool,
+    pub play_count: i64,
+    pub playback_position_ticks: i64,
+    pub is_favorite: bool,
+    pub last_played_date: Option<String>,
+}
+
+/// Body of the user data endpoint.  Values left as None are not changed by the server.
+#[derive(Default, Debug, Clone, Serialize)]
+pub struct UserItemData {
+    #[serde(rename = "Played", skip_serializing_if = "Option::is_none")]
+    pub played: Option<bool>,
+    #[serde(rename = "PlayCount", skip_serializing_if = "Option::is_none")]
+    pub play_count: Option<i64>,
+    #[serde(rename = "PlaybackPositionTicks", skip_serializing_if = "Option::is_none")]
+    pub playback_position_ticks: Option<i64>,
+    #[serde(rename = "IsFavorite", skip_serializing_if = "Option::is_none")]
+    pub is_favorite: Option<bool>,
+    #[serde(rename = "LastPlayedDate", skip_serializing_if = "Option::is_none")]
+    pub last_played_date: Option<String>,
+}
+
+impl WatchStateItem {
+    pub fn from_media_item(item: &MediaItem) -> WatchStateItem {
+        let is_episode = item.type_field == "Episode";
+        let user_data = &item.user_data;
+        WatchStateItem {
+            name: item.name.clone(),
+            item_type: item.type_field.clone(),
+            year: Some(item.production_year).filter(|y| *y > 0),
+            series_name: Some(item.series_name.clone()).filter(|s| is_episode && !s.is_empty()),
+            season: Some(item.parent_index_number).filter(|_| is_episode),
+            episode: Some(item.index_number).filter(|_| is_episode),
+            provider_ids: item.provider_ids.ids.clone(),
+            played: user_data.played,
+            play_count: user_data.play_count,
+            playback_position_ticks: user_data.playback_position_ticks,
+            is_favorite: user_data.is_favorite,
+            last_played_date: Some(user_data.last_played_date.clone()).filter(|d| !d.is_empty()),
+        }
+    }
+}
+
+impl WatchStateExport {
+    pub fn read_file(path: &str) -> Result<WatchStateExport, String> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Unable to read {path}, {e}"))?;
+        serde_json::from_str(&data).map_err(|e| format!("Unable to parse {path}, {e}"))
+    }
+
+    ///
+    /// Writes the export to a file, or to stdout when no file is given.
+    ///
+    pub fn write(&self, path: Option<&str>) -> Result<(), String> {
+        let data = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        match path {
+            None => {
+                println!("{data}");
+                Ok(())
+            }
+            Some(path) => fs::write(path, data).map_err(|e| format!("Unable to write {path}, {e}")),
+        }
+    }
+}
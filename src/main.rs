@@ -1,10 +1,10 @@
-use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::{generate, Shell};
+use std::collections::BTreeMap;
 use std::env;
 use std::fmt;
 use std::io::{self, Write};
-use std::thread;
-use std::time;
+use std::path::PathBuf;
 
 mod user_actions;
 use user_actions::UserAuth;
@@ -27,16 +27,24 @@ use entities::server_info::ServerInfo;
 use entities::user_details::{Policy, UserDetails};
 
 mod utils;
+use utils::config::{load_config, profile_from_args, resolve_config_path};
+use utils::config_migration::{migrate_config, CURRENT_CONFIG_VERSION};
+use utils::identity::set_identity;
+use utils::password_input::read_password;
+use utils::secret_store::unlock_api_key;
 use utils::status_handler::{handle_others, handle_unauthorized};
 
 // All public functions in the below use statements are used within this file, so just get them all.
 mod commands;
-use commands::log_commands::{command_create_report, command_generate_report, command_list_logs};
+use commands::config_commands::{command_config_decrypt, command_config_encrypt, command_config_get, command_config_path, command_config_set, command_config_show, command_config_validate};
+use commands::log_commands::{AuditThresholds, command_create_report, command_generate_report, command_list_logs, command_report_users_audit};
 use commands::media_commands::{command_get_libraries, command_library_enable_disable, command_register_libarary, command_scan_library, command_search_media, command_update_metadata, command_update_image_by_name, command_update_image_by_id};
-use commands::server_commands::{command_apply_backup, command_create_backup, command_execute_task_by_name, command_get_backups, command_get_devices, command_get_packages, command_get_plugins, command_get_repositories, command_get_scheduled_tasks, command_initialize, command_install_package, command_register_repository, command_server_setup, token_to_api};
-use commands::user_commands::{command_add_user, command_add_users, command_delete_user, command_disable_user, command_enable_user, command_grant_admin, command_list_users, command_remove_device_by_username, command_reset_password, command_revoke_admin, command_update_users, command_update_profile_picture};
-
-use crate::user_actions::UserAuthQuickconnect;
+use commands::migrate_commands::{MigrationOptions, command_users_migrate};
+use commands::parental_commands::{command_parental_report, command_parental_set, ParentalChanges};
+use commands::role_commands::{command_role_apply, command_role_diff, command_role_list};
+use commands::server_commands::{command_apply_backup, command_create_api_key, command_list_api_keys, command_revoke_api_key, command_rotate_api_key, command_create_backup, command_execute_task_by_name, command_get_backups, command_get_devices, command_get_packages, command_get_plugins, command_get_repositories, command_get_scheduled_tasks, command_initialize, command_install_package, command_quickconnect, command_quickconnect_authorize, command_quickconnect_status, command_register_repository, command_server_setup, token_to_api};
+use commands::user_commands::{command_add_user, command_add_user_generated, command_add_users, command_delete_user, command_disable_user, command_enable_user, command_grant_admin, command_list_users, command_remove_device_by_username, command_reset_password, command_revoke_admin, command_update_users, command_update_profile_picture, command_user_access_grant, command_user_access_revoke, command_user_access_set, command_user_access_show, command_user_policy_get, command_user_policy_set, command_user_schedule_add, command_user_schedule_clear, command_user_schedule_list, command_user_schedule_remove, command_user_prefs_get, command_user_prefs_set, command_user_rename, command_user_set_lockout, command_user_unlock, command_users_apply, command_users_locked, command_users_plan, command_users_prune, command_users_restore, UnmanagedUsers};
+use commands::watch_commands::{command_played_set, command_progress_reset, command_watch_state_export, command_watch_state_import, command_watch_state_import_external};
 
 #[macro_use]
 extern crate serde_derive;
@@ -46,30 +54,55 @@ extern crate serde_derive;
 //
 const USER_POLICY: &str = "/Users/{userId}/Policy";
 const USER_ID: &str = "/Users/{userId}";
+const USER_CONFIGURATION: &str = "/Users/{userId}/Configuration";
 const USERS: &str = "/Users";
 const DEVICES: &str = "/Devices";
 const BACKUPS: &str = "/Backup";
+const API_KEYS: &str = "/Auth/Keys";
+const PARENTAL_RATINGS: &str = "/Localization/ParentalRatings";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct AppConfig {
+    // Files written before versioning was introduced have no version and are treated as version 0.
+    #[serde(default)]
+    config_version: u32,
     status: String,
     comfy: bool,
     server_url: String,
     os: String,
     api_key: String,
     token: String,
+    // Populated instead of api_key when the API key is encrypted at rest.
+    encrypted_api_key: String,
+    passphrase_file: String,
+    device_name: String,
+    device_id: String,
+    // Role last applied to each user, used by "role diff" to find users that have drifted.
+    role_assignments: BTreeMap<String, String>,
+    #[serde(skip)]
+    config_path: PathBuf,
+    #[serde(skip)]
+    passphrase: Option<String>,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         AppConfig {
+            config_version: CURRENT_CONFIG_VERSION,
             status: "not configured".to_owned(),
             comfy: true,
             server_url: "Unknown".to_owned(),
             os: "Unknown".to_owned(),
             api_key: "Unknown".to_owned(),
             token: "Unknown".to_owned(),
+            encrypted_api_key: String::new(),
+            passphrase_file: String::new(),
+            device_name: "jellyroller".to_owned(),
+            device_id: String::new(),
+            role_assignments: BTreeMap::new(),
+            config_path: PathBuf::new(),
+            passphrase: None,
         }
     }
 }
@@ -83,6 +116,10 @@ struct Cli {
     /// Enable verbose output for debugging (shows HTTP requests/responses)
     #[clap(short, long, global = true)]
     verbose: bool,
+
+    /// Use the named configuration profile instead of the default configuration
+    #[clap(long, global = true)]
+    profile: Option<String>,
     
     #[clap(subcommand)]
     command: Commands,
@@ -96,15 +133,40 @@ enum Commands {
         /// Username to create.
         #[clap(required = true, value_parser)]
         username: String,
-        /// Password for created user.
-        #[clap(required = true, value_parser)]
-        password: String,
+        /// Password for created user.  Prompted for when not supplied.  Visible in shell history, prefer --password-stdin or --password-file.
+        #[clap(value_parser, conflicts_with_all = ["password_stdin", "password_file"])]
+        password: Option<String>,
+        #[clap(flatten)]
+        password_input: PasswordArgs,
+        /// Generate a random password for the user
+        #[clap(long, conflicts_with_all = ["password", "password_stdin", "password_file"])]
+        generate_password: bool,
+        #[clap(flatten)]
+        generation: PasswordGenerationArgs,
     },
     /// Uses the supplied file to mass create new users.  
     AddUsers {
-        /// File that contains the user information in "username,password" lines.
-        #[clap(required = true, value_parser)]
+        /// CSV or JSON file that contains the users to create.
+        /// CSV files with a header row may use the columns username, password, admin, disabled,
+        /// hidden, libraries (library names separated by ';'), max_parental_rating and remote_access.
+        /// JSON files contain an array of objects with the same fields, with libraries as an array.
+        /// CSV files without a header are read as "username,password" lines.
+        /// Users without a password will prompt for one.
+        #[clap(required = true, value_parser, verbatim_doc_comment)]
         inputfile: String,
+        /// Generate a random password for every user, ignoring any passwords in the file
+        #[clap(long)]
+        generate_passwords: bool,
+        /// Keep creating the remaining users when one fails
+        #[clap(long)]
+        continue_on_error: bool,
+        #[clap(flatten)]
+        generation: PasswordGenerationArgs,
+    },
+    /// Manage server API keys.
+    ApiKey {
+        #[clap(subcommand)]
+        command: ApiKeyCommands,
     },
 
     /// Applies the specified backup.
@@ -118,6 +180,11 @@ enum Commands {
         #[clap(required = true, value_parser)]
         shell: Shell,
     },
+    /// Inspect or edit the JellyRoller configuration.
+    Config {
+        #[clap(subcommand)]
+        command: ConfigCommands,
+    },
     /// Creates a new backup (metadata, trickplay, subtitles, database)
     CreateBackup {
         /// Skip backing up metadata
@@ -142,32 +209,37 @@ enum Commands {
         #[clap(required = false, short, long, default_value = "")]
         filename: String,
     },
-    /// Deletes an existing user.
+    /// Deletes existing users.
     #[clap(arg_required_else_help = true)]
     DeleteUser {
-        /// User to remove.
-        #[clap(required = true, value_parser)]
-        username: String,
+        #[clap(flatten)]
+        selector: UserSelector,
+        /// Skip the confirmation asked for when users are chosen by a selector rather than by name
+        #[clap(long)]
+        yes: bool,
     },
     /// Disable a library.
     DisableLibrary {
         #[clap(required = true, value_parser)]
         library: String,
     },
-    /// Disable a user.
+    /// Disable users.
     DisableUser {
-        #[clap(required = true, value_parser)]
-        username: String,
+        #[clap(flatten)]
+        selector: UserSelector,
+        /// Skip the confirmation asked for when users are chosen by a selector rather than by name
+        #[clap(long)]
+        yes: bool,
     },
     /// Enable a library
     EnableLibrary {
         #[clap(required = true, value_parser)]
         library: String,
     },
-    /// Enable a user.
+    /// Enable users.
     EnableUser {
-        #[clap(required = true, value_parser)]
-        username: String,
+        #[clap(flatten)]
+        selector: UserSelector,
     },
     /// Executes a scheduled task by name.
     ExecuteTaskByName {
@@ -221,19 +293,24 @@ enum Commands {
         #[clap(short = 'o', long, value_enum, default_value = "table")]
         output_format: OutputFormat,
     },
-    /// Grants the specified user admin rights.
+    /// Grants the specified users admin rights.
     GrantAdmin {
-        #[clap(required = true, value_parser)]
-        username: String,
+        #[clap(flatten)]
+        selector: UserSelector,
+        /// Skip the confirmation asked for when users are chosen by a selector rather than by name
+        #[clap(long)]
+        yes: bool,
     },
     /// Perform a silent initialization.
     Initialize {
         /// Username for API key creation
         #[clap(required = true, long = "username")]
         username: String,
-        /// Password for user
-        #[clap(required = true, long = "password")]
-        password: String,
+        /// Password for user.  Prompted for when not supplied.  Visible in shell history, prefer --password-stdin or --password-file.
+        #[clap(long = "password", conflicts_with_all = ["password_stdin", "password_file"])]
+        password: Option<String>,
+        #[clap(flatten)]
+        password_input: PasswordArgs,
         /// URL of server
         #[clap(required = true, long = "url")]
         server_url: String
@@ -268,8 +345,35 @@ enum Commands {
         #[clap(short, long, default_value = "")]
         username: String,
     },
+    /// Manages parental controls: rating limits, blocked tags and unrated items.
+    Parental {
+        #[clap(subcommand)]
+        command: ParentalCommands,
+    },
+    /// Marks items played or unplayed for a user.
+    Played {
+        #[clap(subcommand)]
+        command: PlayedCommands,
+    },
+    /// Manages how far a user is into items.
+    Progress {
+        #[clap(subcommand)]
+        command: ProgressCommands,
+    },
     /// Authenticate via QuickConnect.
-    Quickconnect {},
+    /// Exits with 2 if the timeout is reached, 3 if the request expires or is denied
+    /// and 4 if QuickConnect is disabled on the server.
+    #[clap(verbatim_doc_comment, args_conflicts_with_subcommands = true)]
+    Quickconnect {
+        #[clap(subcommand)]
+        command: Option<QuickconnectCommands>,
+        /// URL of server.  Prompted for when not supplied.
+        #[clap(long = "url")]
+        server_url: Option<String>,
+        /// Number of seconds to wait for the code to be approved.
+        #[clap(long, default_value = "300")]
+        timeout: u64,
+    },
     /// Reconfigure the connection information.
     Reconfigure {},
     /// Registers a new library.
@@ -295,10 +399,18 @@ enum Commands {
         #[clap(required = true, short = 'u', long = "url")]
         path: String,
     },
-    /// Removes all devices associated with the specified user.
+    /// Removes all devices associated with the specified users.
     RemoveDeviceByUsername {
-        #[clap(required = true, value_parser)]
-        username: String,
+        #[clap(flatten)]
+        selector: UserSelector,
+        /// Skip the confirmation asked for when users are chosen by a selector rather than by name
+        #[clap(long)]
+        yes: bool,
+    },
+    /// Reports that combine information from several parts of the server.
+    Report {
+        #[clap(subcommand)]
+        command: ReportCommands,
     },
     /// Resets a user's password.
     #[clap(arg_required_else_help = true)]
@@ -306,17 +418,27 @@ enum Commands {
         /// User to be modified.
         #[clap(required = true, value_parser)]
         username: String,
-        /// What to reset the specified user's password to.
-        #[clap(required = true, value_parser)]
-        password: String,
+        /// What to reset the specified user's password to.  Prompted for when not supplied.  Visible in shell history, prefer --password-stdin or --password-file.
+        #[clap(value_parser, conflicts_with_all = ["password_stdin", "password_file"])]
+        password: Option<String>,
+        #[clap(flatten)]
+        password_input: PasswordArgs,
     },
-    /// Revokes admin rights from the specified user.
+    /// Revokes admin rights from the specified users.
     RevokeAdmin {
-        #[clap(required = true, value_parser)]
-        username: String,
+        #[clap(flatten)]
+        selector: UserSelector,
+        /// Skip the confirmation asked for when users are chosen by a selector rather than by name
+        #[clap(long)]
+        yes: bool,
     },
     /// Restarts Jellyfin
     RestartJellyfin {},
+    /// Applies reusable policy templates (roles) to users.
+    Role {
+        #[clap(subcommand)]
+        command: RoleCommands,
+    },
     /// Start a library scan.
     ScanLibrary {
         /// Library ID
@@ -419,9 +541,666 @@ enum Commands {
         /// Image file to be used for profile picture
         #[clap(required = true, short, long)]
         path: String
+    },
+    /// Manages individual user accounts.
+    User {
+        #[clap(subcommand)]
+        command: UserCommands,
+    },
+    /// Displays or changes the libraries a user can access.
+    UserAccess {
+        #[clap(subcommand)]
+        command: UserAccessCommands,
+    },
+    /// Displays or changes individual user policy settings.
+    UserPolicy {
+        #[clap(subcommand)]
+        command: UserPolicyCommands,
+    },
+    /// Displays or changes the hours during which users can sign in.
+    UserSchedule {
+        #[clap(subcommand)]
+        command: UserScheduleCommands,
+    },
+    /// Manages users in bulk: manifests, migration, lockouts and pruning.
+    Users {
+        #[clap(subcommand)]
+        command: UsersCommands,
+    },
+    /// Exports or imports what a user has played, started and marked as favorite.
+    WatchState {
+        #[clap(subcommand)]
+        command: WatchStateCommands,
+    },
+}
+
+/// Alternatives to passing a password on the command line.  The JELLYROLLER_PASSWORD environment
+/// variable is also checked before prompting.
+#[derive(Debug, Args)]
+struct PasswordArgs {
+    /// Read the password from the first line of standard input
+    #[clap(long, conflicts_with = "password_file")]
+    password_stdin: bool,
+    /// Read the password from the first line of the specified file
+    #[clap(long)]
+    password_file: Option<String>,
+}
+
+/// Options for generated passwords and where the resulting credentials are written.  Only accepted together
+/// with the option that asks for passwords to be generated.
+#[derive(Debug, Args)]
+struct PasswordGenerationArgs {
+    /// Length of generated passwords (16 when not supplied)
+    #[clap(long)]
+    password_length: Option<usize>,
+    /// Character classes used in generated passwords (all of them when not supplied)
+    #[clap(long, value_enum, value_delimiter = ',')]
+    password_classes: Vec<CharClass>,
+    /// File to write generated credentials to (created with 0600 permissions).  Printed to stdout when not supplied.
+    #[clap(long)]
+    credentials_file: Option<String>,
+    /// Format of the generated credentials (csv when not supplied)
+    #[clap(long, value_enum)]
+    credentials_format: Option<CredentialsFormat>,
+}
+
+impl UserSelector {
+    ///
+    /// Whether users are chosen by --all or a filter rather than only by name, in which case commands that change
+    /// them ask for confirmation first.
+    ///
+    fn is_bulk(&self) -> bool {
+        self.all || self.pattern.is_some() || self.admins || self.disabled || self.inactive_since.is_some() || self.from_file.is_some()
+    }
+
+    ///
+    /// Selector for commands that only accept usernames and --match.  With neither, every user is selected.
+    ///
+    fn by_name(users: Vec<String>, pattern: Option<String>) -> UserSelector {
+        UserSelector { users, pattern, all: false, admins: false, disabled: false, inactive_since: None, from_file: None }
+    }
+}
+
+impl PasswordGenerationArgs {
+    ///
+    /// Exits when any generation option was given to a command that was not asked to generate passwords, as the
+    /// options would otherwise be ignored and users created without the intended password.
+    ///
+    fn require_generation(&self, generate: bool, flag: &str) {
+        let customized = self.password_length.is_some()
+            || !self.password_classes.is_empty()
+            || self.credentials_file.is_some()
+            || self.credentials_format.is_some();
+        if customized && !generate {
+            eprintln!("[ERROR] --password-length, --password-classes, --credentials-file and --credentials-format require {flag}.");
+            std::process::exit(2);
+        }
     }
 }
 
+#[derive(Debug, Subcommand)]
+enum ApiKeyCommands {
+    /// Lists all API keys registered on the server.
+    List {
+        /// Specify the output format
+        #[clap(short = 'o', long, value_enum, default_value = "table")]
+        output_format: OutputFormat,
+    },
+    /// Creates a new API key.
+    Create {
+        /// Application name the key is issued to.
+        #[clap(required = true, long)]
+        app: String,
+    },
+    /// Revokes API keys by token or application name.
+    Revoke {
+        /// Access token or application name to revoke.
+        #[clap(required = true, value_parser)]
+        key: String,
+    },
+    /// Replaces JellyRoller's API key with a new one and revokes the old key.
+    Rotate {},
+}
+
+#[derive(Debug, Subcommand)]
+enum RoleCommands {
+    /// Lists the available roles and where they are stored.
+    List {},
+    /// Applies a role to the selected users.
+    Apply {
+        /// Role to apply.
+        #[clap(required = true)]
+        role: String,
+        #[clap(flatten)]
+        selector: UserSelector,
+        /// Skip the confirmation asked for when users are chosen by a selector rather than by name
+        #[clap(long)]
+        yes: bool,
+    },
+    /// Reports users whose policy no longer matches the role.
+    ///
+    /// Without users or --match, every user the role was applied to is checked.
+    /// Exits with code 2 when a user has drifted.
+    Diff {
+        /// Role to compare against.
+        #[clap(required = true)]
+        role: String,
+        /// Users to compare.
+        #[clap(conflicts_with = "pattern")]
+        users: Vec<String>,
+        /// Compare every user whose name matches this regular expression
+        #[clap(long = "match")]
+        pattern: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum UserAccessCommands {
+    /// Displays the libraries a user can access.
+    Show {
+        /// User to display.
+        #[clap(required = true)]
+        username: String,
+    },
+    /// Gives a user access to additional libraries.
+    Grant {
+        /// User to modify.
+        #[clap(required = true)]
+        username: String,
+        /// Library name, may be repeated
+        #[clap(long = "library", required_unless_present = "all", conflicts_with = "all")]
+        libraries: Vec<String>,
+        /// Give access to all libraries, including ones added later
+        #[clap(long)]
+        all: bool,
+    },
+    /// Removes a user's access to libraries.
+    Revoke {
+        /// User to modify.
+        #[clap(required = true)]
+        username: String,
+        /// Library name, may be repeated
+        #[clap(long = "library", required_unless_present = "all", conflicts_with = "all")]
+        libraries: Vec<String>,
+        /// Remove access to all libraries
+        #[clap(long)]
+        all: bool,
+    },
+    /// Replaces a user's library access with exactly the specified libraries.
+    Set {
+        /// User to modify.
+        #[clap(required = true)]
+        username: String,
+        /// Library name, may be repeated
+        #[clap(long = "library", required = true)]
+        libraries: Vec<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum UserPolicyCommands {
+    /// Displays the policy of a user, or a single policy value.
+    Get {
+        /// User to display.
+        #[clap(required = true)]
+        username: String,
+        /// Policy key to display, e.g. EnableRemoteAccess.
+        key: Option<String>,
+    },
+    /// Changes one or more policy values of a user.
+    Set {
+        /// User to modify.
+        #[clap(required = true)]
+        username: String,
+        /// Settings in Key=Value form, e.g. EnableRemoteAccess=false MaxActiveSessions=2.
+        /// Lists such as EnabledFolders take comma separated values.
+        #[clap(required = true, num_args = 1.., verbatim_doc_comment)]
+        settings: Vec<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum UserScheduleCommands {
+    /// Displays the access schedules of a user.
+    List {
+        /// User to display.
+        #[clap(required = true)]
+        username: String,
+    },
+    /// Adds an access schedule to users.
+    Add {
+        #[clap(flatten)]
+        targets: ScheduleTargets,
+        #[clap(flatten)]
+        window: ScheduleWindow,
+    },
+    /// Removes a matching access schedule from users.
+    Remove {
+        #[clap(flatten)]
+        targets: ScheduleTargets,
+        #[clap(flatten)]
+        window: ScheduleWindow,
+    },
+    /// Removes all access schedules from users, allowing them to sign in at any time.
+    Clear {
+        #[clap(flatten)]
+        targets: ScheduleTargets,
+    },
+}
+
+/// Users a command applies to.  Named users and users read from a file are combined; --match,
+/// --admins, --disabled and --inactive-since then narrow down the named users, or every user
+/// when none are named.
+#[derive(Debug, Args)]
+#[group(required = true, multiple = true)]
+struct UserSelector {
+    /// Users to act on.
+    users: Vec<String>,
+    /// Select users whose name matches this regular expression
+    #[clap(long = "match")]
+    pattern: Option<String>,
+    /// Select every user, or every user matching the other selectors.  Cannot be combined with named users.
+    #[clap(long, conflicts_with_all = ["users", "from_file"])]
+    all: bool,
+    /// Select administrators
+    #[clap(long)]
+    admins: bool,
+    /// Select disabled users
+    #[clap(long)]
+    disabled: bool,
+    /// Select users without activity since this date (YYYY-MM-DD)
+    #[clap(long)]
+    inactive_since: Option<String>,
+    /// Read the users to act on from a file, one name per line
+    #[clap(long)]
+    from_file: Option<String>,
+}
+
+/// Items a played or progress command applies to.  Search, library and series may be combined.
+#[derive(Debug, Args)]
+#[group(required = true, multiple = true)]
+struct ItemSelector {
+    /// Item ids to act on
+    #[clap(long = "id", value_delimiter = ',', conflicts_with_all = ["search", "parent", "series"])]
+    ids: Vec<String>,
+    /// Select movies and episodes whose name contains this term
+    #[clap(long)]
+    search: Option<String>,
+    /// Select movies and episodes in this library
+    #[clap(long)]
+    parent: Option<String>,
+    /// Select the episodes of the series with exactly this name
+    #[clap(long)]
+    series: Option<String>,
+}
+
+/// Users an access schedule command applies to.
+#[derive(Debug, Args)]
+struct ScheduleTargets {
+    /// Users to modify.
+    #[clap(required_unless_present = "role", conflicts_with = "role")]
+    users: Vec<String>,
+    /// Modify every user the role has been applied to
+    #[clap(long)]
+    role: Option<String>,
+}
+
+/// A day and hour range during which a user can sign in.
+#[derive(Debug, Args)]
+struct ScheduleWindow {
+    /// Day the schedule applies to
+    #[clap(long, value_enum, required = true)]
+    day: ScheduleDay,
+    /// Hour access starts, from 0 to 24.  Half hours such as 7.5 are allowed.
+    #[clap(long, required = true)]
+    from: f64,
+    /// Hour access ends, from 0 to 24.
+    #[clap(long, required = true)]
+    to: f64,
+}
+
+#[derive(Debug, Subcommand)]
+enum UserCommands {
+    /// Displays or changes a user's preferences (subtitle and audio languages, autoplay and more).
+    Prefs {
+        #[clap(subcommand)]
+        command: UserPrefsCommands,
+    },
+    /// Changes a user's name.
+    Rename {
+        /// Current username.
+        #[clap(required = true)]
+        username: String,
+        /// New username.
+        #[clap(required = true)]
+        new_name: String,
+    },
+    /// Re-enables users locked out by failed logins and clears their failed login count.
+    Unlock {
+        #[clap(flatten)]
+        selector: UserSelector,
+    },
+    /// Sets how many failed logins lock a user out: -1 never locks, 0 uses the server default.
+    SetLockout {
+        #[clap(flatten)]
+        selector: UserSelector,
+        /// Failed logins allowed before the account is locked.
+        #[clap(long, required = true, allow_negative_numbers = true)]
+        attempts: i64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum UserPrefsCommands {
+    /// Displays a user's preferences, or a single preference.
+    Get {
+        /// User to inspect.
+        #[clap(required = true)]
+        username: String,
+        /// Preference to display, e.g. SubtitleMode
+        key: Option<String>,
+    },
+    /// Changes preferences of the selected users.
+    ///
+    /// Preferences are given as --set Key=Value, e.g.
+    ///   jellyroller user prefs set alice bob --set SubtitleLanguagePreference=eng --set SubtitleMode=Smart
+    ///   jellyroller user prefs set --all --set EnableNextEpisodeAutoPlay=false
+    #[clap(verbatim_doc_comment)]
+    Set {
+        #[clap(flatten)]
+        selector: UserSelector,
+        /// Preference to change as Key=Value, may be repeated
+        #[clap(long = "set", required = true, value_name = "KEY=VALUE")]
+        settings: Vec<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum UsersCommands {
+    /// Shows the changes needed to make the server's users match the manifest.
+    Plan {
+        /// YAML manifest describing the desired users.
+        #[clap(short = 'f', long = "file", required = true)]
+        manifest: String,
+        /// Also delete users that are not in the manifest, except administrators
+        #[clap(long)]
+        delete_unmanaged: bool,
+        /// Delete administrators that are not in the manifest as well
+        #[clap(long, requires = "delete_unmanaged")]
+        include_admins: bool,
+    },
+    /// Makes the server's users match the manifest.
+    ///
+    /// Users without a password in the manifest are created with a generated password.
+    Apply {
+        /// YAML manifest describing the desired users.
+        #[clap(short = 'f', long = "file", required = true)]
+        manifest: String,
+        /// Also delete users that are not in the manifest, except administrators
+        #[clap(long)]
+        delete_unmanaged: bool,
+        /// Delete administrators that are not in the manifest as well
+        #[clap(long, requires = "delete_unmanaged")]
+        include_admins: bool,
+        /// Apply the changes without asking for confirmation
+        #[clap(long)]
+        auto_approve: bool,
+        #[clap(flatten)]
+        generation: PasswordGenerationArgs,
+    },
+    /// Copies users, with their policy, preferences and profile picture, to the server of another profile.
+    ///
+    /// Library and channel access is translated by name.  Anything that cannot be translated is
+    /// listed in a report at the end.  Passwords cannot be copied, so users are created without
+    /// one unless --generate-passwords is given.
+    #[clap(verbatim_doc_comment)]
+    Migrate {
+        /// Profile to copy users from, defaults to the active configuration
+        #[clap(long)]
+        from: Option<String>,
+        /// Profile of the server to copy users to
+        #[clap(long, required = true)]
+        to: String,
+        /// Users to copy, defaults to every user
+        #[clap(long, num_args = 1.., value_delimiter = ',')]
+        users: Vec<String>,
+        /// Give the new users generated placeholder passwords
+        #[clap(long)]
+        generate_passwords: bool,
+        #[clap(flatten)]
+        generation: PasswordGenerationArgs,
+        /// Only show what would be migrated and the mapping report
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Lists users that are locked out by failed logins or one attempt away from it.
+    Locked {
+        /// Specify the output format
+        #[clap(short = 'o', long, value_enum, default_value = "table")]
+        output_format: OutputFormat,
+    },
+    /// Disables, or deletes, users that have not been active for a number of days.
+    ///
+    /// Administrators are never pruned.  Changes are recorded in a journal next to the
+    /// configuration file so that disabled users can be brought back with "users restore".
+    #[clap(verbatim_doc_comment)]
+    Prune {
+        /// Prune users without activity for at least this many days
+        #[clap(long, default_value = "90", value_parser = clap::value_parser!(i64).range(1..))]
+        inactive_days: i64,
+        /// Delete the inactive users instead of disabling them
+        #[clap(long)]
+        delete: bool,
+        /// Usernames to leave alone, "*" matches any characters (e.g. admin,svc-*)
+        #[clap(long, value_delimiter = ',')]
+        exclude: Vec<String>,
+        /// Prune without asking for confirmation
+        #[clap(long)]
+        yes: bool,
+    },
+    /// Re-enables users disabled by "users prune".  Lists the users that can be restored when none are given.
+    Restore {
+        /// Users to enable again.
+        #[clap(conflicts_with = "all")]
+        users: Vec<String>,
+        /// Restore every user disabled by "users prune"
+        #[clap(long)]
+        all: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigCommands {
+    /// Displays the path of the configuration file in use.
+    Path {},
+    /// Displays the current configuration with the API key redacted.
+    Show {},
+    /// Displays a single configuration value.  The API key is redacted unless --reveal is given.
+    Get {
+        /// Configuration key (e.g. server_url, comfy)
+        #[clap(required = true, value_parser)]
+        key: String,
+        /// Print the API key instead of redacting it
+        #[clap(long)]
+        reveal: bool,
+    },
+    /// Updates a single configuration value.
+    Set {
+        /// Configuration key: api_key, comfy, device_name, passphrase_file or server_url
+        #[clap(required = true, value_parser)]
+        key: String,
+        /// New value for the key.
+        #[clap(required = true, value_parser)]
+        value: String,
+    },
+    /// Checks the configuration for missing or invalid values.
+    Validate {},
+    /// Encrypts the stored API key with a passphrase.
+    /// The passphrase is read from JELLYROLLER_PASSPHRASE, the file named by
+    /// JELLYROLLER_PASSPHRASE_FILE or the passphrase_file setting, or prompted for.
+    #[clap(verbatim_doc_comment)]
+    Encrypt {},
+    /// Stores the API key in plaintext again.
+    Decrypt {},
+}
+
+#[derive(Debug, Subcommand)]
+enum ParentalCommands {
+    /// Changes the parental controls of one or more users.
+    Set {
+        /// Users to modify.
+        #[clap(required = true)]
+        users: Vec<String>,
+        /// Highest rating the users may watch, by name (e.g. PG-13), or "none" to remove the limit
+        #[clap(long)]
+        max_rating: Option<String>,
+        /// Block items with this tag, may be repeated
+        #[clap(long = "block-tag")]
+        block_tags: Vec<String>,
+        /// Stop blocking items with this tag, may be repeated
+        #[clap(long = "unblock-tag")]
+        unblock_tags: Vec<String>,
+        /// Block unrated items of these types
+        #[clap(long, value_enum, value_delimiter = ',', ignore_case = true)]
+        block_unrated: Vec<UnratedItem>,
+        /// Stop blocking unrated items of these types
+        #[clap(long, value_enum, value_delimiter = ',', ignore_case = true)]
+        allow_unrated: Vec<UnratedItem>,
+    },
+    /// Shows the parental restrictions of every user side by side.
+    Report {
+        /// Specify the output format
+        #[clap(short = 'o', long, value_enum, default_value = "table")]
+        output_format: OutputFormat,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum QuickconnectCommands {
+    /// Approves a QuickConnect code shown on another device.
+    Authorize {
+        /// Code displayed on the device.
+        #[clap(required = true, value_parser)]
+        code: String,
+        /// User to sign the device in as.  Required because the API key JellyRoller signs in with belongs to no user.
+        #[clap(short, long, required = true)]
+        user: String,
+    },
+    /// Shows whether QuickConnect is enabled on the server.
+    Status {},
+}
+
+#[derive(Debug, Subcommand)]
+enum ReportCommands {
+    /// Audits user accounts for admins, inactivity, missing passwords, remote access and failed logins.
+    UsersAudit {
+        /// Flag users without activity for at least this many days
+        #[clap(long, default_value = "90")]
+        inactive_days: i64,
+        /// Flag users with at least this many failed logins
+        #[clap(long, default_value = "3")]
+        failed_logins: i64,
+        /// Number of activity log entries to examine
+        #[clap(long, default_value = "1000")]
+        activity_limit: String,
+        /// Only show users with at least one flag
+        #[clap(long)]
+        flagged_only: bool,
+        /// Specify the output format
+        #[clap(short = 'o', long, value_enum, default_value = "table")]
+        output_format: OutputFormat,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum PlayedCommands {
+    /// Marks the selected items played.
+    Mark {
+        /// User whose items are marked
+        #[clap(short, long, required = true)]
+        user: String,
+        #[clap(flatten)]
+        items: ItemSelector,
+        /// Only list the items that would be marked
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Marks the selected items unplayed.
+    Unmark {
+        /// User whose items are marked
+        #[clap(short, long, required = true)]
+        user: String,
+        #[clap(flatten)]
+        items: ItemSelector,
+        /// Only list the items that would be marked
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ProgressCommands {
+    /// Clears the playback position of the selected items so they start from the beginning.
+    Reset {
+        /// User whose progress is reset
+        #[clap(short, long, required = true)]
+        user: String,
+        #[clap(flatten)]
+        items: ItemSelector,
+        /// Only list the items that would be reset
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum WatchStateCommands {
+    /// Writes the played state, play counts, playback positions and favorites of a user to JSON.
+    Export {
+        /// User whose watch state is exported
+        #[clap(required = true)]
+        username: String,
+        /// File to write, or stdout when not given
+        #[clap(short = 'o', long)]
+        output: Option<String>,
+    },
+    /// Applies an exported watch state to a user, matching items by provider ids or name and year.
+    Import {
+        /// User the watch state is applied to
+        #[clap(required = true)]
+        username: String,
+        /// File written by watch-state export
+        #[clap(required = true)]
+        file: String,
+        /// Only show what would be updated
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Marks items played from a Trakt or Letterboxd CSV export, matching them by provider ids or title and year.
+    ImportExternal {
+        /// Service the export was downloaded from
+        #[clap(long, value_enum, required = true)]
+        format: HistoryFormat,
+        /// CSV file exported from the service
+        #[clap(required = true)]
+        file: String,
+        /// User the history is applied to
+        #[clap(short, long, required = true)]
+        user: String,
+        /// Only show what would be updated
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+enum CharClass {
+    Lower,
+    Upper,
+    Digits,
+    Symbols,
+}
+
 #[derive(ValueEnum, Clone, Debug, PartialEq)]
 enum CollectionType {
     Movies,
@@ -434,6 +1213,45 @@ enum CollectionType {
     Mixed,
 }
 
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+enum CredentialsFormat {
+    Csv,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+enum HistoryFormat {
+    Trakt,
+    Letterboxd,
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+enum ScheduleDay {
+    Sunday,
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Everyday,
+    Weekday,
+    Weekend,
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+enum UnratedItem {
+    Movie,
+    Trailer,
+    Series,
+    Music,
+    Book,
+    LiveTvChannel,
+    LiveTvProgram,
+    ChannelContent,
+    Other,
+}
+
 #[derive(ValueEnum, Clone, Debug, PartialEq)]
 enum Detail {
     User,
@@ -480,36 +1298,57 @@ enum ScanType {
 }
 
 fn main() -> Result<(), confy::ConfyError> {
-    let mut current = env::current_exe().unwrap();
-    current.pop();
-    current.push("jellyroller.config");
-
-    let cfg: AppConfig = if std::path::Path::new(current.as_path()).exists() {
-        confy::load_path(current.as_path())?
-    } else {
-        confy::load("jellyroller", "jellyroller")?
-    };
+    let args: Vec<String> = env::args().collect();
+    let profile = profile_from_args(&args);
+    if profile.as_ref().is_some_and(|p| p.is_empty() || p.contains(['/', '\\', '.'])) {
+        eprintln!("[ERROR] Profile names cannot be empty or contain '/', '\\' or '.'.");
+        std::process::exit(1);
+    }
+    let mut cfg: AppConfig = load_config(resolve_config_path(profile.as_deref())?)?;
 
     // Due to an oddity with confy and clap, manually check for help flag.
-    let args: Vec<String> = env::args().collect();
-    if !(args.contains(&"initialize".to_string()) || args.contains(&"server-setup".to_string()) || args.contains(&"quickconnect".to_string()) || args.contains(&"-h".to_string()) || args.contains(&"--help".to_string())) {
+    if !(args.contains(&"initialize".to_string()) || args.contains(&"server-setup".to_string()) || args.contains(&"quickconnect".to_string()) || args.contains(&"config".to_string()) || args.contains(&"-h".to_string()) || args.contains(&"--help".to_string())) {
         if cfg.status == "not configured" {
             println!("Application is not configured!");
             initial_config(cfg);
             std::process::exit(0);
-        } else if cfg.token == "Unknown" {
-            println!("[INFO] Username/Password detected.  Reconfiguring to use API key.");
-            token_to_api(cfg.clone());
         }
+        cfg = migrate_config(cfg);
     }
+    // After migrating, so that an older configuration gets its device id from the version 2 migration.
+    set_identity(&mut cfg);
 
     // Attempting to setup ability to execute certain commands prior to initialization
     let args = Cli::parse();
     
     // Initialize verbose mode
     utils::debug::set_verbose(args.verbose);
+
+    // Decrypt the API key for everything other than commands that do not talk to the server or replace the key.
+    if !matches!(
+        args.command,
+        Commands::Completions { .. }
+            | Commands::Initialize { .. }
+            | Commands::Reconfigure {}
+            | Commands::Quickconnect { command: None, .. }
+            | Commands::ServerSetup { .. }
+            | Commands::Config { command: ConfigCommands::Path {} | ConfigCommands::Show {} | ConfigCommands::Encrypt {} }
+    ) {
+        unlock_api_key(&mut cfg);
+    }
     
     match args.command {
+        // Config Commands
+        Commands::Config { command } => match command {
+            ConfigCommands::Path {} => command_config_path(&cfg),
+            ConfigCommands::Show {} => command_config_show(&cfg),
+            ConfigCommands::Get { key, reveal } => command_config_get(&cfg, &key, reveal),
+            ConfigCommands::Set { key, value } => command_config_set(&cfg, &key, &value),
+            ConfigCommands::Validate {} => command_config_validate(&cfg),
+            ConfigCommands::Encrypt {} => command_config_encrypt(cfg),
+            ConfigCommands::Decrypt {} => command_config_decrypt(cfg),
+        },
+
         // Log Commands
         Commands::CreateReport { report_type, limit, filename } => command_create_report(&cfg, &report_type, &limit, filename),
         Commands::GenerateReport {} => command_generate_report(&cfg),
@@ -528,6 +1367,12 @@ fn main() -> Result<(), confy::ConfyError> {
         Commands::UpdateImageById { id, path, imagetype } => command_update_image_by_id(&cfg, &id, path, &imagetype),
         
         // Server Commands
+        Commands::ApiKey { command } => match command {
+            ApiKeyCommands::List { output_format } => command_list_api_keys(&cfg, &output_format, API_KEYS),
+            ApiKeyCommands::Create { app } => command_create_api_key(&cfg, &app, API_KEYS),
+            ApiKeyCommands::Revoke { key } => command_revoke_api_key(&cfg, &key, API_KEYS),
+            ApiKeyCommands::Rotate {} => command_rotate_api_key(cfg, API_KEYS),
+        },
         Commands::ApplyBackup { filename } => command_apply_backup(&cfg, &filename),
         Commands::CreateBackup { skip_metadata, skip_trickplay, skip_subtitles} => command_create_backup(&cfg, !skip_metadata, !skip_trickplay, !skip_subtitles),
         Commands::ExecuteTaskByName { task } => command_execute_task_by_name(&cfg, &task),
@@ -537,9 +1382,36 @@ fn main() -> Result<(), confy::ConfyError> {
         Commands::GetPlugins { output_format} => command_get_plugins(cfg, &output_format),
         Commands::GetRepositories { output_format } => command_get_repositories(&cfg, &output_format),
         Commands::GetScheduledTasks { output_format } => command_get_scheduled_tasks(&cfg, &output_format),
-        Commands::Initialize { username, password, server_url } => command_initialize(cfg, &username, password, &server_url),
+        Commands::Initialize { username, password, password_input, server_url } => {
+            let password = read_password(password, &password_input, &format!("Please enter the password for {username}: "), false);
+            command_initialize(cfg, &username, password, &server_url);
+        }
         Commands::InstallPackage { package, version, repository} => command_install_package(&cfg, &package, &version, &repository),
-        Commands::Quickconnect {} => process_quickconnect(cfg),
+        Commands::Parental { command } => match command {
+            ParentalCommands::Set { users, max_rating, block_tags, unblock_tags, block_unrated, allow_unrated } => {
+                let changes = ParentalChanges {
+                    max_rating: max_rating.as_deref(),
+                    block_tags: &block_tags,
+                    unblock_tags: &unblock_tags,
+                    block_unrated: &block_unrated,
+                    allow_unrated: &allow_unrated,
+                };
+                command_parental_set(&cfg, &users, &changes, USERS, USER_POLICY, PARENTAL_RATINGS);
+            }
+            ParentalCommands::Report { output_format } => command_parental_report(&cfg, &output_format, USERS, PARENTAL_RATINGS),
+        },
+        Commands::Played { command } => match command {
+            PlayedCommands::Mark { user, items, dry_run } => command_played_set(&cfg, &user, &items, true, dry_run, USERS),
+            PlayedCommands::Unmark { user, items, dry_run } => command_played_set(&cfg, &user, &items, false, dry_run, USERS),
+        },
+        Commands::Progress { command } => match command {
+            ProgressCommands::Reset { user, items, dry_run } => command_progress_reset(&cfg, &user, &items, dry_run, USERS),
+        },
+        Commands::Quickconnect { command, server_url, timeout } => match command {
+            None => command_quickconnect(cfg, server_url, timeout),
+            Some(QuickconnectCommands::Authorize { code, user }) => command_quickconnect_authorize(&cfg, &code, &user, USERS),
+            Some(QuickconnectCommands::Status {}) => command_quickconnect_status(&cfg),
+        },
         Commands::Reconfigure {} => initial_config(cfg),
         Commands::RegisterRepository { name, path } => command_register_repository(&cfg, name, path),
         Commands::RestartJellyfin {} => restart_or_shutdown(ServerInfo::new("/System/Restart",&cfg.server_url,&cfg.api_key,)),
@@ -548,18 +1420,101 @@ fn main() -> Result<(), confy::ConfyError> {
         Commands::ShutdownJellyfin {} => restart_or_shutdown(ServerInfo::new("/System/Shutdown",&cfg.server_url,&cfg.api_key,)),
 
         // User commands
-        Commands::AddUser { username, password } => command_add_user(&cfg, username, password),
-        Commands::AddUsers { inputfile } => command_add_users(&cfg, inputfile),
-        Commands::DeleteUser { username } => command_delete_user(cfg, username),
-        Commands::DisableUser { username } => command_disable_user(&cfg, &username, USER_POLICY, USER_ID),
-        Commands::EnableUser { username } => command_enable_user(&cfg, &username, USER_POLICY, USER_ID),
-        Commands::GrantAdmin { username } => command_grant_admin(&cfg, &username, USER_POLICY, USER_ID),
+        Commands::AddUser { username, password, password_input, generate_password, generation } => {
+            generation.require_generation(generate_password, "--generate-password");
+            if generate_password {
+                command_add_user_generated(&cfg, username, &generation);
+            } else {
+                let password = read_password(password, &password_input, &format!("Please enter the password for {username}: "), true);
+                command_add_user(&cfg, username, password);
+            }
+        }
+        Commands::AddUsers { inputfile, generate_passwords, continue_on_error, generation } => {
+            generation.require_generation(generate_passwords, "--generate-passwords");
+            command_add_users(&cfg, &inputfile, generate_passwords.then_some(&generation), continue_on_error, USERS, USER_POLICY);
+        }
+        Commands::DeleteUser { selector, yes } => command_delete_user(&cfg, &selector, yes, USERS),
+        Commands::DisableUser { selector, yes } => command_disable_user(&cfg, &selector, yes, USERS, USER_POLICY),
+        Commands::EnableUser { selector } => command_enable_user(&cfg, &selector, USERS, USER_POLICY),
+        Commands::GrantAdmin { selector, yes } => command_grant_admin(&cfg, &selector, yes, USERS, USER_POLICY),
         Commands::ListUsers { export, output, username } => command_list_users(&cfg, export, output, &username, USERS, USER_ID),
-        Commands::RemoveDeviceByUsername { username } => command_remove_device_by_username(&cfg, &username, DEVICES),
-        Commands::ResetPassword { username, password } => command_reset_password(cfg, &username, password, USERS),
-        Commands::RevokeAdmin { username } => command_revoke_admin(&cfg, &username, USER_POLICY, USER_ID),
+        Commands::RemoveDeviceByUsername { selector, yes } => command_remove_device_by_username(&cfg, &selector, yes, USERS, DEVICES),
+        Commands::ResetPassword { username, password, password_input } => {
+            let password = read_password(password, &password_input, &format!("Please enter the new password for {username}: "), true);
+            command_reset_password(cfg, &username, password, USERS);
+        }
+        Commands::Report { command } => match command {
+            ReportCommands::UsersAudit { inactive_days, failed_logins, activity_limit, flagged_only, output_format } => {
+                let thresholds = AuditThresholds { inactive_days, failed_logins };
+                command_report_users_audit(&cfg, &thresholds, &activity_limit, flagged_only, &output_format, USERS);
+            }
+        },
+        Commands::RevokeAdmin { selector, yes } => command_revoke_admin(&cfg, &selector, yes, USERS, USER_POLICY),
         Commands::UpdateUsers { inputfile } => command_update_users(&cfg, inputfile, USER_ID),
         Commands::UpdateUserProfilePicture { username, path } => command_update_profile_picture(&cfg, &username, &path),
+        Commands::Role { command } => match command {
+            RoleCommands::List {} => command_role_list(&cfg),
+            RoleCommands::Apply { role, selector, yes } => command_role_apply(cfg, &role, &selector, yes, USERS, USER_POLICY),
+            RoleCommands::Diff { role, users, pattern } => command_role_diff(&cfg, &role, &users, pattern.as_deref(), USERS),
+        },
+        Commands::User { command } => match command {
+            UserCommands::Prefs { command: UserPrefsCommands::Get { username, key } } => command_user_prefs_get(&cfg, &username, key.as_deref(), USERS),
+            UserCommands::Prefs { command: UserPrefsCommands::Set { selector, settings } } => {
+                command_user_prefs_set(&cfg, &selector, &settings, USERS, USER_CONFIGURATION);
+            },
+            UserCommands::Rename { username, new_name } => command_user_rename(cfg, &username, &new_name, USERS, USER_ID),
+            UserCommands::Unlock { selector } => command_user_unlock(&cfg, &selector, USERS, USER_POLICY),
+            UserCommands::SetLockout { selector, attempts } => command_user_set_lockout(&cfg, &selector, attempts, USERS, USER_POLICY),
+        },
+        Commands::UserAccess { command } => match command {
+            UserAccessCommands::Show { username } => command_user_access_show(&cfg, &username, USERS),
+            UserAccessCommands::Grant { username, libraries, all } => command_user_access_grant(&cfg, &username, &libraries, all, USERS, USER_POLICY),
+            UserAccessCommands::Revoke { username, libraries, all } => command_user_access_revoke(&cfg, &username, &libraries, all, USERS, USER_POLICY),
+            UserAccessCommands::Set { username, libraries } => command_user_access_set(&cfg, &username, &libraries, USERS, USER_POLICY),
+        },
+        Commands::UserPolicy { command } => match command {
+            UserPolicyCommands::Get { username, key } => command_user_policy_get(&cfg, &username, key.as_deref(), USERS),
+            UserPolicyCommands::Set { username, settings } => command_user_policy_set(&cfg, &username, &settings, USERS, USER_POLICY),
+        },
+        Commands::UserSchedule { command } => match command {
+            UserScheduleCommands::List { username } => command_user_schedule_list(&cfg, &username, USERS),
+            UserScheduleCommands::Add { targets, window } => command_user_schedule_add(&cfg, &targets, &window, USERS, USER_POLICY),
+            UserScheduleCommands::Remove { targets, window } => command_user_schedule_remove(&cfg, &targets, &window, USERS, USER_POLICY),
+            UserScheduleCommands::Clear { targets } => command_user_schedule_clear(&cfg, &targets, USERS, USER_POLICY),
+        },
+        Commands::Users { command } => match command {
+            UsersCommands::Plan { manifest, delete_unmanaged, include_admins } => {
+                command_users_plan(&cfg, &manifest, UnmanagedUsers::from_flags(delete_unmanaged, include_admins), USERS);
+            }
+            UsersCommands::Apply { manifest, delete_unmanaged, include_admins, auto_approve, generation } => {
+                let unmanaged = UnmanagedUsers::from_flags(delete_unmanaged, include_admins);
+                command_users_apply(&cfg, &manifest, unmanaged, auto_approve, &generation, USERS, USER_POLICY);
+            }
+            UsersCommands::Migrate { from, to, users, generate_passwords, generation, dry_run } => {
+                generation.require_generation(generate_passwords, "--generate-passwords");
+                let options = MigrationOptions {
+                    from: from.as_deref(),
+                    to: &to,
+                    usernames: &users,
+                    generate_passwords,
+                    generation: &generation,
+                    dry_run,
+                };
+                command_users_migrate(&cfg, &options, USERS, USER_POLICY, USER_CONFIGURATION);
+            }
+            UsersCommands::Locked { output_format } => command_users_locked(&cfg, &output_format, USERS),
+            UsersCommands::Prune { inactive_days, delete, exclude, yes } => {
+                command_users_prune(&cfg, inactive_days, delete, &exclude, yes, USERS, USER_POLICY);
+            }
+            UsersCommands::Restore { users, all } => command_users_restore(&cfg, &users, all, USERS, USER_POLICY),
+        },
+        Commands::WatchState { command } => match command {
+            WatchStateCommands::Export { username, output } => command_watch_state_export(&cfg, &username, output.as_deref(), USERS),
+            WatchStateCommands::Import { username, file, dry_run } => command_watch_state_import(&cfg, &username, &file, dry_run, USERS),
+            WatchStateCommands::ImportExternal { format, file, user, dry_run } => {
+                command_watch_state_import_external(&cfg, &user, &file, &format, dry_run, USERS);
+            }
+        },
         
         // Other
         Commands::Completions { shell } => {
@@ -577,6 +1532,12 @@ fn main() -> Result<(), confy::ConfyError> {
 /// the user.
 ///
 fn initial_config(mut cfg: AppConfig) {
+    set_identity(&mut cfg);
+    // The new API key replaces an encrypted one, which is how a forgotten passphrase is recovered from.
+    let was_encrypted = !cfg.encrypted_api_key.is_empty();
+    cfg.encrypted_api_key = String::new();
+    cfg.passphrase = None;
+
     println!("[INFO] Attempting to determine Jellyfin information.....");
     env::consts::OS.clone_into(&mut cfg.os);
     println!("[INFO] OS detected as {}.", cfg.os);
@@ -603,31 +1564,48 @@ fn initial_config(mut cfg: AppConfig) {
     "configured".clone_into(&mut cfg.status);
     println!("[INFO] Converting token to api");
     token_to_api(cfg);
+    if was_encrypted {
+        println!("[INFO] The new API key is stored unencrypted.  Run 'jellyroller config encrypt' to encrypt it.");
+    }
 }
 
-fn process_quickconnect(mut cfg: AppConfig) {
-    println!("[INFO] QuickConnect auth in progress...");
-    print!("[INPUT] Please enter your Jellyfin URL:  ");
-    io::stdout().flush().expect("Unable to get Jellyfin URL.");
-    let mut server_url_input = String::new();
-    io::stdin()
-        .read_line(&mut server_url_input)
-        .expect("Could not read server url information");
-    server_url_input.trim().clone_into(&mut cfg.server_url);
-    println!("[INFO] Attempting to initialize a QuickConnect request.....");
-    let mut details = UserAuthQuickconnect::quickconnect_initiate(UserAuthQuickconnect::new(&cfg.server_url)).unwrap();
-    println!("Your login code is: {}", details.code.clone());
-    // Wait while the QuickConnect code is approved.  
-    while !&details.authenticated {
-        let result = UserAuthQuickconnect::quickconnect_get_status(&details, &cfg.server_url).unwrap();
-        details.authenticated = result.authenticated;
-        thread::sleep(time::Duration::from_secs(1));
+///
+/// Custom implementation to convert the `UnratedItem` enum into the item types Jellyfin uses.
+///
+impl fmt::Display for UnratedItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnratedItem::Movie => write!(f, "Movie"),
+            UnratedItem::Trailer => write!(f, "Trailer"),
+            UnratedItem::Series => write!(f, "Series"),
+            UnratedItem::Music => write!(f, "Music"),
+            UnratedItem::Book => write!(f, "Book"),
+            UnratedItem::LiveTvChannel => write!(f, "LiveTvChannel"),
+            UnratedItem::LiveTvProgram => write!(f, "LiveTvProgram"),
+            UnratedItem::ChannelContent => write!(f, "ChannelContent"),
+            UnratedItem::Other => write!(f, "Other"),
+        }
     }
-    // Now that we are authenticated we need to tie our QuickConnect to the account
-    cfg.api_key = UserAuthQuickconnect::quickconnect_authenticate(&details, &cfg.server_url).unwrap();
-    "configured".clone_into(&mut cfg.status);
-    token_to_api(cfg);
+}
 
+///
+/// Custom implementation to convert the `ScheduleDay` enum into the day names Jellyfin uses.
+///
+impl fmt::Display for ScheduleDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScheduleDay::Sunday => write!(f, "Sunday"),
+            ScheduleDay::Monday => write!(f, "Monday"),
+            ScheduleDay::Tuesday => write!(f, "Tuesday"),
+            ScheduleDay::Wednesday => write!(f, "Wednesday"),
+            ScheduleDay::Thursday => write!(f, "Thursday"),
+            ScheduleDay::Friday => write!(f, "Friday"),
+            ScheduleDay::Saturday => write!(f, "Saturday"),
+            ScheduleDay::Everyday => write!(f, "Everyday"),
+            ScheduleDay::Weekday => write!(f, "Weekday"),
+            ScheduleDay::Weekend => write!(f, "Weekend"),
+        }
+    }
 }
 
 ///
@@ -1,5 +1,5 @@
 use reqwest::{blocking::{Client, Response}, header::{CONTENT_TYPE, HeaderMap}};
-use crate::utils::debug::{log_request, log_response};
+use crate::utils::{debug::{log_request, log_response}, identity::authorization_header};
 
 pub fn simple_get(server_url: String, api_key: &str, query: Vec<(&str, &str)>) -> Response {
     log_request("GET", &server_url, None);
@@ -7,7 +7,7 @@ pub fn simple_get(server_url: String, api_key: &str, query: Vec<(&str, &str)>) -
     let client = Client::new();
     let response = client
         .get(&server_url)
-        .header("Authorization", format!("MediaBrowser Token=\"{api_key}\""))
+        .header("Authorization", authorization_header(api_key))
         .query(&query)
         .send();
     if let Ok(resp) = response {
@@ -27,13 +27,8 @@ pub fn simple_post(server_url: String, api_key: &str, body: String, content_type
     let mut headers: HeaderMap = HeaderMap::new();
     headers.insert(CONTENT_TYPE, content_type.parse().unwrap());
 
-    if api_key.is_empty() {
-        // Used when JellyRoller is not yet authenticated.
-        headers.insert("Authorization","MediaBrowser Client=\"JellyRoller\", Device=\"jellyroller\", DeviceId=\"1\", Version=\"0.0.1\"".parse().unwrap());
-    } else {
-        // Used after JellyRoller has been authenticated.
-        headers.insert("Authorization", format!("MediaBrowser Token=\"{api_key}\"").parse().unwrap());
-    }
+    // The token is left out when JellyRoller is not yet authenticated.
+    headers.insert("Authorization", authorization_header(api_key).parse().unwrap());
 
     let client = Client::new();
     let response = client
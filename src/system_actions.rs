@@ -1,6 +1,7 @@
 use crate::{ReportType, entities::{
-    activity_details::ActivityDetails, backup_details::{BackupDetails, BackupRootJson}, library_details::{LibraryDetails, LibraryDetailsVec}, library_options::LibraryOptionsRoot, media_details::MediaRoot, repository_details::RepositoryDetails, task_details::TaskDetails
-}};
+    activity_details::ActivityDetails, backup_details::{BackupDetails, BackupRootJson}, library_details::{LibraryDetails, LibraryDetailsVec}, library_options::LibraryOptionsRoot, media_details::MediaRoot, repository_details::RepositoryDetails, task_details::TaskDetails,
+    parental_details::ParentalRating, token_details::{TokenDetails, TokenItem}, watch_state::UserItemData
+}, utils::identity::authorization_header};
 
 use super::{
     handle_others, handle_unauthorized,
@@ -378,7 +379,7 @@ pub fn remove_device(server_info: ServerInfo, id: &str) -> Result<(), reqwest::E
     let apikey = &server_info.api_key;
     let response = client
         .delete(server_info.server_url)
-        .header("Authorization", format!("MediaBrowser Token=\"{apikey}\""))
+        .header("Authorization", authorization_header(apikey))
         .query(&[("id", &id)])
         .send()?;
     match response.status() {
@@ -395,6 +396,82 @@ pub fn remove_device(server_info: ServerInfo, id: &str) -> Result<(), reqwest::E
     Ok(())
 }
 
+pub fn get_api_keys(server_info: ServerInfo) -> Result<Vec<TokenItem>, Box<dyn std::error::Error>> {
+    let response = simple_get(server_info.server_url, &server_info.api_key, Vec::new());
+    let mut keys = Vec::new();
+    match response.status() {
+        StatusCode::OK => {
+            keys = response.json::<TokenDetails>()?.items;
+        }
+        StatusCode::UNAUTHORIZED => {
+            handle_unauthorized();
+        }
+        _ => {
+            handle_others(&response);
+        }
+    }
+    Ok(keys)
+}
+
+pub fn get_parental_ratings(server_info: ServerInfo) -> Result<Vec<ParentalRating>, Box<dyn std::error::Error>> {
+    let response = simple_get(server_info.server_url, &server_info.api_key, Vec::new());
+    let mut ratings = Vec::new();
+    match response.status() {
+        StatusCode::OK => {
+            ratings = response.json::<Vec<ParentalRating>>()?;
+        }
+        StatusCode::UNAUTHORIZED => {
+            handle_unauthorized();
+        }
+        _ => {
+            handle_others(&response);
+        }
+    }
+    Ok(ratings)
+}
+
+pub fn create_api_key(server_info: &ServerInfo, app: &str) {
+    let response = simple_post(
+        server_info.server_url.clone(),
+        &server_info.api_key,
+        String::new(),
+        "application/json",
+        &[("app", app)],
+    );
+    match response.status() {
+        StatusCode::NO_CONTENT => {
+            println!("API key for \"{app}\" created.");
+        }
+        StatusCode::UNAUTHORIZED => {
+            handle_unauthorized();
+        }
+        _ => {
+            handle_others(&response);
+        }
+    }
+}
+
+pub fn revoke_api_key(server_info: &ServerInfo, key: &str) -> Result<(), reqwest::Error> {
+    let client = Client::new();
+    let apikey = &server_info.api_key;
+    let response = client
+        .delete(format!("{}/{key}", server_info.server_url))
+        .header("Authorization", authorization_header(apikey))
+        .send()?;
+    match response.status() {
+        StatusCode::NO_CONTENT => {
+            println!("API key {key} revoked.");
+        }
+        StatusCode::UNAUTHORIZED => {
+            handle_unauthorized();
+        }
+        _ => {
+            handle_others(&response);
+        }
+    }
+    Ok(())
+}
+
 pub fn get_scheduled_tasks(server_info: ServerInfo) -> Result<Vec<TaskDetails>, reqwest::Error> {
     let response = simple_get(server_info.server_url, &server_info.api_key, Vec::new());
     let mut details = Vec::new();
@@ -564,7 +641,7 @@ impl LogFile {
         let response = client
             .get(self.server_info.server_url)
             .query(&[("name", self.logname)])
-            .header("Authorization", format!("MediaBrowser Token=\"{apikey}\""))
+            .header("Authorization", authorization_header(apikey))
             .send()?;
         match response.status() {
             StatusCode::OK => {
@@ -581,3 +658,90 @@ impl LogFile {
         Ok(())
     }
 }
+
+pub fn get_channels(server_info: ServerInfo) -> Result<MediaRoot, Box<dyn std::error::Error>> {
+    let response = simple_get(server_info.server_url, &server_info.api_key, Vec::new());
+    match response.status() {
+        StatusCode::OK => Ok(response.json::<MediaRoot>()?),
+        StatusCode::UNAUTHORIZED => {
+            handle_unauthorized();
+            std::process::exit(1);
+        }
+        status => Err(format!("Unable to get channels, status: {status}").into()),
+    }
+}
+
+///
+/// Downloads the primary image of a user.
+///
+pub fn get_user_image(server_info: ServerInfo, id: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let response = simple_get(server_info.server_url.replace("{userId}", id), &server_info.api_key, Vec::new());
+    match response.status() {
+        StatusCode::OK => Ok(response.bytes()?.to_vec()),
+        StatusCode::UNAUTHORIZED => {
+            handle_unauthorized();
+            std::process::exit(1);
+        }
+        status => Err(format!("Unable to download image, status: {status}").into()),
+    }
+}
+
+///
+/// Replaces the primary image of a user, returning any failure to the caller.
+///
+pub fn set_user_image(server_info: ServerInfo, id: &str, img_base64: String) -> Result<(), Box<dyn std::error::Error>> {
+    let response = simple_post(
+        server_info.server_url.replace("{userId}", id),
+        &server_info.api_key,
+        img_base64,
+        "image/png",
+        &Vec::new()
+    );
+    match response.status() {
+        StatusCode::NO_CONTENT | StatusCode::OK => Ok(()),
+        StatusCode::UNAUTHORIZED => {
+            handle_unauthorized();
+            Ok(())
+        }
+        status => Err(format!("Unable to upload image, status: {status}").into()),
+    }
+}
+
+pub fn update_user_item_data(server_info: &ServerInfo, item_id: &str, user_id: &str, data: &UserItemData) -> Result<(), Box<dyn std::error::Error>> {
+    let response = simple_post(
+        server_info.server_url.replace("{itemId}", item_id),
+        &server_info.api_key,
+        serde_json::to_string(data)?,
+        "application/json",
+        &[("userId", user_id)]
+    );
+    match response.status() {
+        StatusCode::OK | StatusCode::NO_CONTENT => Ok(()),
+        StatusCode::UNAUTHORIZED => {
+            handle_unauthorized();
+            Ok(())
+        }
+        status => Err(format!("Unable to update user data, status: {status}").into()),
+    }
+}
+
+pub fn set_item_played(server_info: &ServerInfo, item_id: &str, user_id: &str, played: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let url = server_info.server_url.replace("{itemId}", item_id);
+    let response = if played {
+        simple_post(url, &server_info.api_key, String::new(), "application/json", &[("userId", user_id)])
+    } else {
+        Client::new()
+            .delete(url)
+            .header("Authorization", authorization_header(&server_info.api_key))
+            .query(&[("userId", user_id)])
+            .send()?
+    };
+    match response.status() {
+        StatusCode::OK | StatusCode::NO_CONTENT => Ok(()),
+        StatusCode::UNAUTHORIZED => {
+            handle_unauthorized();
+            Ok(())
+        }
+        status => Err(format!("Unable to update played state, status: {status}").into()),
+    }
+}
@@ -1,5 +1,7 @@
 use crate::entities::token_details::TokenDetails;
 use crate::entities::quickconnect_details::QuickConnectDetails;
+use crate::entities::user_details::Configuration;
+use crate::utils::identity::authorization_header;
 
 use super::{
     handle_others, handle_unauthorized,
@@ -18,7 +20,9 @@ pub struct UserWithPass {
     pass: Option<String>,
     #[serde(rename = "CurrentPw")]
     currentpwd: Option<String>,
+    #[serde(skip)]
     server_url: String,
+    #[serde(skip)]
     auth_key: String,
 }
 
@@ -65,10 +69,12 @@ impl UserWithPass {
     }
 
     pub fn create_user(self) -> Result<(), Box<dyn std::error::Error>> {
+        // /Users/New expects "Password" rather than the "NewPw" used when resetting a password.
+        let body = serde_json::json!({ "Name": self.username, "Password": self.pass });
         let response = simple_post(
             self.server_url.clone(),
             &self.auth_key.clone(),
-            serde_json::to_string_pretty(&self)?,
+            serde_json::to_string_pretty(&body)?,
             "application/json",
             &Vec::new()
         );
@@ -80,7 +86,8 @@ impl UserWithPass {
                 handle_unauthorized();
             }
             _ => {
-                handle_others(&response);
+                // Reported to the caller so that bulk creation can account for users that were not created.
+                return Err(format!("Request failed with status: {}", response.status()).into());
             }
         }
 
@@ -92,7 +99,7 @@ impl UserWithPass {
         let apikey = &self.auth_key;
         let response = client
             .delete(self.server_url)
-            .header("Authorization", format!("MediaBrowser Token=\"{apikey}\""))
+            .header("Authorization", authorization_header(apikey))
             .header(CONTENT_TYPE, "application/json")
             .send()?;
         match response.status() {
@@ -103,7 +110,7 @@ impl UserWithPass {
                 handle_unauthorized();
             }
             _ => {
-                handle_others(&response);
+                return Err(format!("Request failed with status: {}", response.status()).into());
             }
         }
 
@@ -115,7 +122,7 @@ impl UserWithPass {
         let apikey = &self.auth_key;
         let response = client
             .post(self.server_url)
-            .header("Authorization", format!("MediaBrowser Token=\"{apikey}\""))
+            .header("Authorization", authorization_header(apikey))
             .header(CONTENT_LENGTH, 0)
             .query(&[("app", "JellyRoller")])
             .send()
@@ -185,7 +192,7 @@ impl UserAuth {
         let response = client
             .post(self.server_url.clone())
             .header(CONTENT_TYPE, "application/json")
-            .header("Authorization", "MediaBrowser Client=\"JellyRoller\", Device=\"jellyroller\", DeviceId=\"1\", Version=\"0.0.1\"")
+            .header("Authorization", authorization_header(""))
             .body(serde_json::to_string_pretty(&self)?)
             .send()?;
 
@@ -201,6 +208,12 @@ impl UserAuth {
     }
 }
 
+pub enum QuickConnectState {
+    Pending,
+    Authenticated,
+    Expired,
+}
+
 pub struct UserAuthQuickconnect {
     server_url: String
 }
@@ -212,7 +225,7 @@ impl UserAuthQuickconnect {
         }
     }
 
-    pub fn quickconnect_initiate(self) -> Result<QuickConnectDetails, Box<dyn std::error::Error>> {
+    pub fn quickconnect_initiate(self) -> Result<Option<QuickConnectDetails>, Box<dyn std::error::Error>> {
         let response = simple_post(
             format!("{0}/QuickConnect/Initiate", self.server_url),
             "",
@@ -221,30 +234,75 @@ impl UserAuthQuickconnect {
             &Vec::new()
         );
 
+        // Jellyfin refuses to start a request when QuickConnect is disabled.
+        match response.status() {
+            StatusCode::OK => Ok(Some(response.json::<QuickConnectDetails>()?)),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Ok(None),
+            _ => {
+                handle_others(&response);
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn quickconnect_enabled(self) -> Result<bool, Box<dyn std::error::Error>> {
+        let response = simple_get(
+            format!("{0}/QuickConnect/Enabled", self.server_url),
+            "",
+            Vec::new()
+        );
         if response.status() == StatusCode::OK {
-            let result = response.json::<QuickConnectDetails>()?;
-            Ok(result)
+            Ok(response.json::<bool>()?)
         } else {
-            panic!("Failed")
+            handle_others(&response);
+            Ok(false)
+        }
+    }
+
+    pub fn quickconnect_authorize(self, api_key: &str, code: &str, user_id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let query = vec![("code", code), ("userId", user_id)];
+        let response = simple_post(
+            format!("{0}/QuickConnect/Authorize", self.server_url),
+            api_key,
+            String::new(),
+            "application/json",
+            &query
+        );
+        match response.status() {
+            StatusCode::OK => Ok(response.json::<bool>()?),
+            StatusCode::NOT_FOUND => Ok(false),
+            StatusCode::UNAUTHORIZED => {
+                handle_unauthorized();
+                Ok(false)
+            }
+            _ => {
+                handle_others(&response);
+                Ok(false)
+            }
         }
     }
 
-    pub fn quickconnect_get_status(details: &QuickConnectDetails, server_url: &str) -> Result<QuickConnectDetails, Box<dyn std::error::Error>> {
+    pub fn quickconnect_get_status(details: &QuickConnectDetails, server_url: &str) -> Result<QuickConnectState, Box<dyn std::error::Error>> {
         let response = simple_get(
             format!("{0}/QuickConnect/Connect", server_url),
             "",
             vec![("secret", &details.secret)]
         );
 
-        // If a 404 is thrown, the QuickConnect request is timed out.
-        if response.status() == StatusCode::OK {
-            let result = response.json::<QuickConnectDetails>()?;
-            Ok(result)
-        } else if response.status() == StatusCode::NOT_FOUND {
-            println!("QuickConnect info not found");
-            std::process::exit(1)
-        } else {
-            panic!("Failed")
+        // If a 404 is thrown, the QuickConnect request has expired or was rejected.
+        match response.status() {
+            StatusCode::OK => {
+                if response.json::<QuickConnectDetails>()?.authenticated {
+                    Ok(QuickConnectState::Authenticated)
+                } else {
+                    Ok(QuickConnectState::Pending)
+                }
+            }
+            StatusCode::NOT_FOUND => Ok(QuickConnectState::Expired),
+            _ => {
+                handle_others(&response);
+                Ok(QuickConnectState::Expired)
+            }
         }
     }
 
@@ -262,7 +320,8 @@ impl UserAuthQuickconnect {
             println!("[INFO] User authenticated successfully.");
             Ok(result.access_token)
         } else {
-            panic!("Failed")
+            handle_others(&response);
+            Ok(String::new())
         }
     }
 }
@@ -322,28 +381,67 @@ impl UserList {
         Ok(serde_json::from_str(response.text()?.as_str())?)
     }
 
-    pub fn update_user_config_bool(
-        self,
-        user_info: &Policy,
-        id: &str,
-        username: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let body = serde_json::to_string_pretty(user_info)?;
+    ///
+    /// Replaces the policy of the specified user, returning any failure to the caller.
+    ///
+    pub fn update_user_policy(self, policy: &Policy, id: &str) -> Result<(), Box<dyn std::error::Error>> {
         let response = simple_post(
             self.server_url.replace("{userId}", id),
-            &self.api_key.clone(),
-            body,
+            &self.api_key,
+            serde_json::to_string_pretty(policy)?,
             "application/json",
             &Vec::new()
         );
-        if response.status() == StatusCode::NO_CONTENT {
-            println!("User {username} successfully updated.");
-        } else {
-            println!("Unable to update user policy information.");
-            println!("Status Code: {}", response.status());
-            println!("{}", response.text()?);
+        match response.status() {
+            StatusCode::NO_CONTENT | StatusCode::OK => Ok(()),
+            StatusCode::UNAUTHORIZED => {
+                handle_unauthorized();
+                Ok(())
+            }
+            status => Err(format!("Unable to update user policy, status: {status}").into()),
+        }
+    }
+
+    ///
+    /// Replaces the details of the specified user, which is how Jellyfin renames users.
+    ///
+    pub fn update_user(self, user: &UserDetails, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let response = simple_post(
+            self.server_url.replace("{userId}", id),
+            &self.api_key,
+            serde_json::to_string_pretty(user)?,
+            "application/json",
+            &Vec::new()
+        );
+        match response.status() {
+            StatusCode::NO_CONTENT | StatusCode::OK => Ok(()),
+            StatusCode::UNAUTHORIZED => {
+                handle_unauthorized();
+                Ok(())
+            }
+            status => Err(format!("Unable to update user, status: {status}").into()),
+        }
+    }
+
+    ///
+    /// Replaces the preferences (configuration) of the specified user.
+    ///
+    pub fn update_user_configuration(self, configuration: &Configuration, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let response = simple_post(
+            self.server_url.replace("{userId}", id),
+            &self.api_key,
+            serde_json::to_string_pretty(configuration)?,
+            "application/json",
+            &Vec::new()
+        );
+        match response.status() {
+            StatusCode::NO_CONTENT | StatusCode::OK => Ok(()),
+            StatusCode::UNAUTHORIZED => {
+                handle_unauthorized();
+                Ok(())
+            }
+            status => Err(format!("Unable to update user preferences, status: {status}").into()),
         }
-        Ok(())
     }
 
     //
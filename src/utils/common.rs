@@ -1,4 +1,5 @@
 use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
 use image::ImageFormat;
 use std::io::Cursor;
 ///
@@ -11,4 +12,37 @@ pub fn image_to_base64(path: String) -> String {
         .write_to(&mut Cursor::new(&mut image_data), ImageFormat::Png)
         .unwrap();
     general_purpose::STANDARD.encode(image_data)
-}
\ No newline at end of file
+}
+
+///
+/// Converts an image downloaded from a server, in whatever format it was stored, into a base64 png image.
+///
+pub fn image_bytes_to_base64(bytes: &[u8]) -> Result<String, String> {
+    let base_img = image::load_from_memory(bytes).map_err(|e| format!("Unable to read image, {e}"))?;
+    let mut image_data: Vec<u8> = Vec::new();
+    base_img
+        .write_to(&mut Cursor::new(&mut image_data), ImageFormat::Png)
+        .map_err(|e| format!("Unable to convert image, {e}"))?;
+    Ok(general_purpose::STANDARD.encode(image_data))
+}
+
+///
+/// Prints the error and exits, for commands that cannot continue after a failure.
+///
+pub fn exit_on_error<T>(result: Result<T, String>) -> T {
+    match result {
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        Ok(i) => i,
+    }
+}
+
+///
+/// Jellyfin reports dates as RFC 3339 strings, with the minimum date standing in for "never".
+///
+pub fn parse_jellyfin_date(date: Option<&str>) -> Option<DateTime<Utc>> {
+    let date = DateTime::parse_from_rfc3339(date?).ok()?.with_timezone(&Utc);
+    (date.timestamp() > 0).then_some(date)
+}
@@ -0,0 +1,87 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use crate::{AppConfig, utils::{config_migration::migrate_config, secret_store::{encrypt_secret, unlock_api_key}}};
+
+///
+/// Determines which configuration file is in use.  A "jellyroller.config" file stored alongside
+/// the executable takes precedence over the default confy location.  Named profiles are kept in
+/// the same location as "jellyroller-<profile>.config" or "<profile>.toml" respectively.
+///
+pub fn resolve_config_path(profile: Option<&str>) -> Result<PathBuf, confy::ConfyError> {
+    let mut current = env::current_exe().unwrap();
+    current.pop();
+    current.push("jellyroller.config");
+
+    if Path::new(current.as_path()).exists() {
+        if let Some(name) = profile {
+            current.set_file_name(format!("jellyroller-{name}.config"));
+        }
+        Ok(current)
+    } else {
+        confy::get_configuration_file_path("jellyroller", profile.unwrap_or("jellyroller"))
+    }
+}
+
+///
+/// Finds the value of the global --profile option.  The configuration has to be loaded before clap
+/// parses the arguments, so the option is located manually.
+///
+pub fn profile_from_args(args: &[String]) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--profile" {
+            return iter.next().cloned();
+        }
+        if let Some(name) = arg.strip_prefix("--profile=") {
+            return Some(name.to_owned());
+        }
+    }
+    None
+}
+
+///
+/// Loads the configuration from the supplied path and remembers where it came from so that
+/// any later changes are written back to the same file.
+///
+pub fn load_config(path: PathBuf) -> Result<AppConfig, confy::ConfyError> {
+    let mut cfg: AppConfig = confy::load_path(&path)?;
+    cfg.config_path = path;
+    Ok(cfg)
+}
+
+///
+/// Loads another profile for commands that work with two servers at once.  The profile is upgraded and its
+/// API key unlocked in the same way as the active configuration.
+///
+pub fn load_profile(profile: &str) -> Result<AppConfig, String> {
+    let path = resolve_config_path(Some(profile)).map_err(|e| format!("Unable to locate profile \"{profile}\", {e}"))?;
+    if !path.exists() {
+        return Err(format!("Profile \"{profile}\" does not exist.  Run 'jellyroller --profile {profile} server-info' to configure it."));
+    }
+    let cfg = load_config(path).map_err(|e| format!("Unable to load profile \"{profile}\", {e}"))?;
+    if cfg.status == "not configured" {
+        return Err(format!("Profile \"{profile}\" is not configured.  Run 'jellyroller --profile {profile} server-info' to configure it."));
+    }
+    let mut cfg = migrate_config(cfg);
+    unlock_api_key(&mut cfg);
+    Ok(cfg)
+}
+
+///
+/// Persists the configuration to the file it was loaded from.  When the API key has been unlocked with a
+/// passphrase it is re-encrypted so that the plaintext key is never written to disk.
+///
+pub fn store_config(cfg: &AppConfig) {
+    let mut stored = cfg.clone();
+    if let Some(passphrase) = &cfg.passphrase {
+        stored.encrypted_api_key = encrypt_secret(&cfg.api_key, passphrase);
+        stored.api_key = String::new();
+    } else if !cfg.encrypted_api_key.is_empty() && !cfg.api_key.is_empty() {
+        // A new API key was obtained without unlocking the old one, so the encrypted copy is stale.
+        println!("[INFO] API key replaced.  Run 'jellyroller config encrypt' to encrypt the new key.");
+        stored.encrypted_api_key = String::new();
+    }
+    confy::store_path(&cfg.config_path, stored)
+        .expect("[ERROR] Unable to store updated configuration.");
+}
@@ -0,0 +1,104 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::{AppConfig, commands::server_commands::convert_token_to_api_key, utils::{config::store_config, identity::generate_device_id}};
+
+/// Version written to newly created configuration files.  Bump this and append to `MIGRATIONS`
+/// whenever the configuration layout changes.
+pub const CURRENT_CONFIG_VERSION: u32 = 2;
+
+struct ConfigMigration {
+    /// Version the configuration is at once the migration has been applied.
+    version: u32,
+    description: &'static str,
+    apply: fn(&mut AppConfig),
+}
+
+/// Ordered list of migrations.  Each one upgrades the configuration from `version - 1` to `version`.
+const MIGRATIONS: &[ConfigMigration] = &[
+    ConfigMigration {
+        version: 1,
+        description: "Convert username/password auth token to an API key",
+        apply: migrate_token_to_api_key,
+    },
+    ConfigMigration {
+        version: 2,
+        description: "Assign a unique device id to this installation",
+        apply: migrate_device_id,
+    },
+];
+
+///
+/// Upgrades the configuration one version at a time until it matches `CURRENT_CONFIG_VERSION`.  A backup of the
+/// configuration file is written before each step and the result is stored after each step, so an interrupted
+/// upgrade resumes where it left off.
+///
+pub fn migrate_config(mut cfg: AppConfig) -> AppConfig {
+    if cfg.config_version > CURRENT_CONFIG_VERSION {
+        eprintln!(
+            "[WARN] Configuration version {} is newer than this version of JellyRoller supports ({CURRENT_CONFIG_VERSION}).",
+            cfg.config_version
+        );
+        return cfg;
+    }
+
+    for migration in MIGRATIONS {
+        if cfg.config_version >= migration.version {
+            continue;
+        }
+        let backup = backup_config(&cfg);
+        println!(
+            "[INFO] Migrating configuration from version {} to {}: {}.",
+            cfg.config_version, migration.version, migration.description
+        );
+        if let Some(path) = backup {
+            println!("[INFO] Previous configuration saved to {}.", path.display());
+        }
+        (migration.apply)(&mut cfg);
+        cfg.config_version = migration.version;
+        store_config(&cfg);
+    }
+    cfg
+}
+
+///
+/// Copies the current configuration file to "<file>.v<version>.bak" next to the original.
+///
+fn backup_config(cfg: &AppConfig) -> Option<PathBuf> {
+    if !cfg.config_path.exists() {
+        return None;
+    }
+    let mut backup = cfg.config_path.clone().into_os_string();
+    backup.push(format!(".v{}.bak", cfg.config_version));
+    let backup = PathBuf::from(backup);
+    match fs::copy(&cfg.config_path, &backup) {
+        Ok(_) => Some(backup),
+        Err(e) => {
+            eprintln!("[ERROR] Unable to back up configuration before migrating, {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/*
+    Migration steps.
+*/
+
+///
+/// Version 0 -> 1.  Configurations created prior to JellyRoller 0.3 stored a user auth token rather than an API key.
+///
+fn migrate_token_to_api_key(cfg: &mut AppConfig) {
+    if cfg.status == "configured" && cfg.token == "Unknown" {
+        println!("[INFO] Username/Password detected.  Reconfiguring to use API key.");
+        convert_token_to_api_key(cfg);
+    }
+}
+
+///
+/// Version 1 -> 2.  Every installation previously reported DeviceId "1", so each one now gets its own id.
+///
+fn migrate_device_id(cfg: &mut AppConfig) {
+    if cfg.device_id.is_empty() {
+        cfg.device_id = generate_device_id();
+    }
+}
@@ -0,0 +1,105 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use rand::seq::{IndexedRandom, SliceRandom};
+
+use crate::{CharClass, CredentialsFormat, PasswordGenerationArgs};
+
+const DEFAULT_PASSWORD_LENGTH: usize = 16;
+const ALL_CLASSES: [CharClass; 4] = [CharClass::Lower, CharClass::Upper, CharClass::Digits, CharClass::Symbols];
+
+#[derive(Serialize)]
+pub struct Credential {
+    pub username: String,
+    pub password: String,
+}
+
+fn class_characters(class: &CharClass) -> &'static [u8] {
+    match class {
+        CharClass::Lower => b"abcdefghijklmnopqrstuvwxyz",
+        CharClass::Upper => b"ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+        CharClass::Digits => b"0123456789",
+        CharClass::Symbols => b"!#$%&()*+-.:;<=>?@[]^_{}~",
+    }
+}
+
+///
+/// Generates a random password of the requested length that contains at least one character from every
+/// requested character class.
+///
+pub fn generate_password(options: &PasswordGenerationArgs) -> String {
+    let mut classes: Vec<&CharClass> = Vec::new();
+    for class in &options.password_classes {
+        if !classes.contains(&class) {
+            classes.push(class);
+        }
+    }
+    if classes.is_empty() {
+        classes = ALL_CLASSES.iter().collect();
+    }
+    let length = options.password_length.unwrap_or(DEFAULT_PASSWORD_LENGTH);
+    if length < classes.len() {
+        eprintln!("[ERROR] Password length must be at least {} for the selected character classes.", classes.len());
+        std::process::exit(1);
+    }
+
+    let mut rng = rand::rng();
+    let all: Vec<u8> = classes.iter().flat_map(|c| class_characters(c).iter().copied()).collect();
+    let mut password: Vec<u8> = classes
+        .iter()
+        .map(|c| *class_characters(c).choose(&mut rng).unwrap())
+        .collect();
+    while password.len() < length {
+        password.push(*all.choose(&mut rng).unwrap());
+    }
+    password.shuffle(&mut rng);
+    String::from_utf8(password).unwrap()
+}
+
+///
+/// Writes generated credentials to the credentials file, readable only by the current user, or prints them
+/// to stdout when no file was requested.  The passwords are not displayed anywhere else.
+///
+pub fn export_credentials(credentials: &[Credential], options: &PasswordGenerationArgs) {
+    if credentials.is_empty() {
+        return;
+    }
+    let data = match options.credentials_format.as_ref().unwrap_or(&CredentialsFormat::Csv) {
+        CredentialsFormat::Json => serde_json::to_string_pretty(credentials).unwrap() + "\n",
+        CredentialsFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for credential in credentials {
+                writer.serialize(credential).expect("Unable to format credentials.");
+            }
+            String::from_utf8(writer.into_inner().unwrap()).unwrap()
+        }
+    };
+
+    match &options.credentials_file {
+        None => print!("{data}"),
+        Some(path) => {
+            let mut open_options = OpenOptions::new();
+            open_options.write(true).create(true).truncate(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+                open_options.mode(0o600);
+                // mode() only applies to new files, so tighten an existing file as well.
+                if let Ok(metadata) = std::fs::metadata(path) {
+                    let mut permissions = metadata.permissions();
+                    permissions.set_mode(0o600);
+                    std::fs::set_permissions(path, permissions).ok();
+                }
+            }
+            let mut file = match open_options.open(path) {
+                Err(e) => {
+                    eprintln!("[ERROR] Unable to create credentials file {path}, {e}");
+                    std::process::exit(1);
+                }
+                Ok(f) => f,
+            };
+            file.write_all(data.as_bytes()).expect("Unable to write credentials.");
+            println!("Credentials for {} user(s) written to {path}.", credentials.len());
+        }
+    }
+}
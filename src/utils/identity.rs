@@ -0,0 +1,45 @@
+use std::sync::OnceLock;
+
+use crate::AppConfig;
+
+/// Device information reported to Jellyfin in the Authorization header.
+struct ClientIdentity {
+    device_name: String,
+    device_id: String,
+}
+
+static IDENTITY: OnceLock<ClientIdentity> = OnceLock::new();
+
+/// Set the device information used for all requests.  A configuration without a device id is given one, which
+/// is saved the next time the configuration is stored.  Only the first call has any effect.
+pub fn set_identity(cfg: &mut AppConfig) {
+    if cfg.device_id.is_empty() {
+        cfg.device_id = generate_device_id();
+    }
+    let _ = IDENTITY.set(ClientIdentity {
+        device_name: cfg.device_name.clone(),
+        device_id: cfg.device_id.clone(),
+    });
+}
+
+/// Generate a new unique device id for this installation.
+pub fn generate_device_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// Build the value of the Authorization header, including the access token when one is supplied.
+pub fn authorization_header(token: &str) -> String {
+    let (device_name, device_id) = IDENTITY
+        .get()
+        .map_or(("jellyroller", ""), |i| (i.device_name.as_str(), i.device_id.as_str()));
+    let mut header = format!(
+        "MediaBrowser Client=\"JellyRoller\", Device=\"{}\", DeviceId=\"{}\", Version=\"{}\"",
+        device_name.replace('"', "'"),
+        device_id,
+        env!("CARGO_PKG_VERSION")
+    );
+    if !token.is_empty() {
+        header.push_str(&format!(", Token=\"{token}\""));
+    }
+    header
+}
@@ -1,4 +1,10 @@
 pub mod common;
+pub mod config;
+pub mod config_migration;
+pub mod credentials;
 pub mod debug;
+pub mod identity;
 pub mod output_writer;
+pub mod password_input;
+pub mod secret_store;
 pub mod status_handler;
@@ -0,0 +1,61 @@
+use std::env;
+use std::fs;
+use std::io;
+
+use crate::PasswordArgs;
+
+/// Environment variable that may hold the password for user commands.
+pub const PASSWORD_ENV: &str = "JELLYROLLER_PASSWORD";
+
+///
+/// Determines the password for a command.  In order of preference: a password passed on the command line,
+/// --password-stdin, --password-file, the JELLYROLLER_PASSWORD environment variable and finally an interactive
+/// prompt that does not echo.  When `confirm` is set, a prompted password must be entered twice.
+///
+pub fn read_password(password: Option<String>, input: &PasswordArgs, prompt: &str, confirm: bool) -> String {
+    if let Some(p) = password {
+        eprintln!("[WARN] Passwords given as arguments are visible in shell history and process listings.  Consider --password-stdin or --password-file.");
+        return p;
+    }
+
+    if input.password_stdin {
+        let mut line = String::new();
+        if let Err(e) = io::stdin().read_line(&mut line) {
+            eprintln!("[ERROR] Unable to read password from standard input, {e}");
+            std::process::exit(1);
+        }
+        return line.trim_end_matches(['\r', '\n']).to_owned();
+    }
+
+    if let Some(path) = &input.password_file {
+        return match fs::read_to_string(path) {
+            Ok(contents) => contents.lines().next().unwrap_or_default().to_owned(),
+            Err(e) => {
+                eprintln!("[ERROR] Unable to read password file {path}, {e}");
+                std::process::exit(1);
+            }
+        };
+    }
+
+    if let Ok(p) = env::var(PASSWORD_ENV)
+        && !p.is_empty() {
+            return p;
+        }
+
+    prompt_password(prompt, confirm)
+}
+
+///
+/// Prompts for a password without echoing it to the terminal.
+///
+pub fn prompt_password(prompt: &str, confirm: bool) -> String {
+    let password = rpassword::prompt_password(format!("[INPUT] {prompt}")).unwrap();
+    if confirm {
+        let again = rpassword::prompt_password("[INPUT] Please confirm the password: ").unwrap();
+        if password != again {
+            eprintln!("[ERROR] Passwords do not match.");
+            std::process::exit(1);
+        }
+    }
+    password
+}
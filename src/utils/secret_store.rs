@@ -0,0 +1,151 @@
+use std::{env, fs};
+
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng, rand_core::RngCore},
+};
+use argon2::Argon2;
+use base64::{Engine as _, engine::general_purpose};
+
+use crate::AppConfig;
+
+/// Environment variable that holds the passphrase used to unlock the API key.
+pub const PASSPHRASE_ENV: &str = "JELLYROLLER_PASSPHRASE";
+/// Environment variable that points to a file containing the passphrase.
+pub const PASSPHRASE_FILE_ENV: &str = "JELLYROLLER_PASSPHRASE_FILE";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+///
+/// Encrypts the secret with AES-256-GCM using a key derived from the passphrase with Argon2id.
+/// The result is base64 encoded as salt | nonce | ciphertext.
+///
+pub fn encrypt_secret(secret: &str, passphrase: &str) -> String {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, &salt));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, secret.as_bytes())
+        .expect("[ERROR] Unable to encrypt API key.");
+
+    let mut data = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    data.extend_from_slice(&salt);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+    general_purpose::STANDARD.encode(data)
+}
+
+///
+/// Reverses `encrypt_secret`.  Fails if the passphrase is wrong or the data has been tampered with.
+///
+pub fn decrypt_secret(encoded: &str, passphrase: &str) -> Result<String, String> {
+    let data = general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|_| "Encrypted API key is not valid base64.".to_owned())?;
+    if data.len() <= SALT_LEN + NONCE_LEN {
+        return Err("Encrypted API key is truncated.".to_owned());
+    }
+    let (salt, rest) = data.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, salt));
+    let plain = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Incorrect passphrase or corrupted API key.".to_owned())?;
+    String::from_utf8(plain).map_err(|_| "Decrypted API key is not valid UTF-8.".to_owned())
+}
+
+///
+/// Retrieves the passphrase from, in order, the JELLYROLLER_PASSPHRASE environment variable, a key file
+/// (JELLYROLLER_PASSPHRASE_FILE or the `passphrase_file` setting) or an interactive prompt.  When `confirm`
+/// is set, a prompted passphrase must be entered twice.
+///
+pub fn obtain_passphrase(cfg: &AppConfig, confirm: bool) -> String {
+    if let Ok(passphrase) = env::var(PASSPHRASE_ENV)
+        && !passphrase.is_empty() {
+            return passphrase;
+        }
+
+    let key_file = env::var(PASSPHRASE_FILE_ENV).unwrap_or_else(|_| cfg.passphrase_file.clone());
+    if !key_file.is_empty() {
+        return match fs::read_to_string(&key_file) {
+            Ok(contents) => contents.trim_end_matches(['\r', '\n']).to_owned(),
+            Err(e) => {
+                eprintln!("[ERROR] Unable to read passphrase file {key_file}, {e}");
+                std::process::exit(1);
+            }
+        };
+    }
+
+    let passphrase = rpassword::prompt_password("[INPUT] Please enter the configuration passphrase: ").unwrap();
+    if confirm {
+        let again = rpassword::prompt_password("[INPUT] Please confirm the configuration passphrase: ").unwrap();
+        if passphrase != again {
+            eprintln!("[ERROR] Passphrases do not match.");
+            std::process::exit(1);
+        }
+    }
+    if passphrase.is_empty() {
+        eprintln!("[ERROR] Passphrase cannot be empty.");
+        std::process::exit(1);
+    }
+    passphrase
+}
+
+///
+/// Decrypts the stored API key into `cfg.api_key` so the rest of the application can use it as normal.
+/// Does nothing when the API key is not encrypted.
+///
+pub fn unlock_api_key(cfg: &mut AppConfig) {
+    if cfg.encrypted_api_key.is_empty() || cfg.passphrase.is_some() {
+        return;
+    }
+    let passphrase = obtain_passphrase(cfg, false);
+    match decrypt_secret(&cfg.encrypted_api_key, &passphrase) {
+        Ok(api_key) => {
+            cfg.api_key = api_key;
+            cfg.passphrase = Some(passphrase);
+        }
+        Err(e) => {
+            eprintln!("[ERROR] Unable to unlock API key.  {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Key<Aes256Gcm> {
+    let mut key = Key::<Aes256Gcm>::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .expect("[ERROR] Unable to derive encryption key.");
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let encrypted = encrypt_secret("0123456789abcdef", "correct horse");
+        assert_ne!(encrypted, "0123456789abcdef");
+        assert_eq!(decrypt_secret(&encrypted, "correct horse").unwrap(), "0123456789abcdef");
+    }
+
+    #[test]
+    fn wrong_passphrase() {
+        let encrypted = encrypt_secret("0123456789abcdef", "correct horse");
+        assert!(decrypt_secret(&encrypted, "battery staple").is_err());
+    }
+
+    #[test]
+    fn truncated_input() {
+        let encrypted = encrypt_secret("0123456789abcdef", "correct horse");
+        let data = general_purpose::STANDARD.decode(&encrypted).unwrap();
+        let header_only = general_purpose::STANDARD.encode(&data[..SALT_LEN + NONCE_LEN]);
+        assert_eq!(decrypt_secret(&header_only, "correct horse").unwrap_err(), "Encrypted API key is truncated.");
+        let cut = general_purpose::STANDARD.encode(&data[..data.len() - 1]);
+        assert!(decrypt_secret(&cut, "correct horse").is_err());
+    }
+}